@@ -0,0 +1,18 @@
+#!/bin/sh
+# Stand-in bot for the external player protocol (see src/player/external.rs).
+# Always picks the first house it is offered and the first legal action.
+while IFS= read -r line; do
+    id=$(printf '%s\n' "$line" | sed -n 's/^{"id":\([0-9]*\).*/\1/p')
+    case "$line" in
+        *'"type":"choose_house"'*)
+            house=$(printf '%s\n' "$line" | sed -n 's/.*"houses":\["\([^"]*\)".*/\1/p')
+            printf '{"id":%s,"house":"%s"}\n' "$id" "$house"
+            ;;
+        *'"actions":[]'*)
+            printf '{"id":%s,"action":null}\n' "$id"
+            ;;
+        *)
+            printf '{"id":%s,"action":0}\n' "$id"
+            ;;
+    esac
+done
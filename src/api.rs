@@ -2,7 +2,8 @@ use reqwest;
 use std::error;
 use std::collections::HashMap;
 use std::fs::File;
-use game::{CardDetails, House};
+use std::path::Path;
+use game::{BonusIcon, Card, CardDetails, House};
 use serde_json;
 use std::fmt;
 
@@ -11,6 +12,9 @@ pub struct Deck {
     pub cards: Vec<String>,
     pub card_details: HashMap<String, CardDetails>,
     pub houses: Vec<House>,
+    // Bonus icons each copy was enhanced with, by its index in `cards`.
+    // Copies that weren't enhanced may be left off the end.
+    pub enhancements: Vec<Vec<BonusIcon>>,
 }
 
 impl Deck {
@@ -19,8 +23,41 @@ impl Deck {
             cards: Vec::new(),
             card_details: HashMap::new(),
             houses: Vec::new(),
+            enhancements: Vec::new(),
         }
     }
+
+    // The copy of a card at `slot` in `cards`
+    pub fn card<'a>(&'a self, slot: usize) -> Card<'a> {
+        let mut card = Card::new(slot, &self.card_details[&self.cards[slot]]);
+        if let Some(icons) = self.enhancements.get(slot) {
+            card.enhancements = icons;
+        }
+        card
+    }
+
+    // How many cards of the deck share each key, e.g. by set or rarity, in
+    // the order the keys first come up
+    pub fn count_by<K: PartialEq, F: Fn(&CardDetails) -> K>(&self, key: F) -> Vec<(K, usize)> {
+        let mut counts: Vec<(K, usize)> = Vec::new();
+        for id in &self.cards {
+            let key = key(&self.card_details[id]);
+            match counts.iter().position(|(k, _)| *k == key) {
+                Some(index) => counts[index].1 += 1,
+                None => counts.push((key, 1)),
+            }
+        }
+        counts
+    }
+
+    // Adds icons to the copy of a card at `index` in `cards` only. Other
+    // copies keep the plain card.
+    pub fn enhance(&mut self, index: usize, icons: Vec<BonusIcon>) {
+        if self.enhancements.len() <= index {
+            self.enhancements.resize(index + 1, Vec::new());
+        }
+        self.enhancements[index].extend(icons);
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -30,13 +67,26 @@ struct DeckLinks {
 
 #[derive(Deserialize, Serialize, Debug)]
 struct DeckDataLinks {
-    cards: Vec<String>
+    cards: Vec<String>,
+    // Left out of decks saved before it was kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    houses: Option<Vec<House>>,
+}
+
+// The icons one copy of a card in the deck was enhanced with
+#[derive(Deserialize, Serialize, Debug)]
+struct CardBonusIcons {
+    card_id: String,
+    bonus_icons: Vec<BonusIcon>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
 struct DeckData {
     pub name: String,
     pub _links: DeckDataLinks,
+    // One entry per enhanced copy, so copies of a card can differ
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bonus_icons: Vec<CardBonusIcons>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -60,7 +110,7 @@ impl error::Error for DeckError {
         "Loading Deck Failed"
     }
 
-    fn cause(&self) -> Option<&error::Error> {
+    fn cause(&self) -> Option<&dyn error::Error> {
         // Generic error, underlying cause isn't tracked.
         None
     }
@@ -83,9 +133,29 @@ pub fn get_deck(deck_id: &str) -> Result<Deck, DeckError> {
             dr
         }
     };
+    deck_from_response(deck_response)
+}
+
+// Loads a deck saved in the same format the API returns
+pub fn load_deck_file(path: &str) -> Result<Deck, DeckError> {
+    let file = File::open(path).map_err(|_| DeckError)?;
+    let deck_response: DeckResponse = serde_json::from_reader(file).map_err(|_| DeckError)?;
+    deck_from_response(deck_response)
+}
+
+// Takes either a path to a deck file or a deck id
+pub fn find_deck(name: &str) -> Result<Deck, DeckError> {
+    if Path::new(name).is_file() {
+        load_deck_file(name)
+    } else {
+        get_deck(name)
+    }
+}
+
+fn deck_from_response(deck_response: DeckResponse) -> Result<Deck, DeckError> {
     let mut deck = Deck::new();
     for details in deck_response._linked.cards {
-        let house = details.house;
+        let house = details.house.clone();
         deck.card_details.insert(details.id.clone(), details);
         if !deck.houses.contains(&house) {
             deck.houses.push(house);
@@ -94,19 +164,46 @@ pub fn get_deck(deck_id: &str) -> Result<Deck, DeckError> {
     for id in deck_response.data._links.cards {
         deck.cards.push(id)
     }
-    assert_eq!(deck.houses.len(), 3);
+    // Mavericks are listed under the house they were moved to, so every
+    // card counts towards the deck's houses. The deck's own list is used when
+    // there is one, in case a card still names a house it has left.
+    if let Some(houses) = deck_response.data._links.houses {
+        deck.houses = houses;
+    }
+    // Each entry is a different copy, so it goes to the next copy of the card
+    // that hasn't been enhanced yet
+    let mut enhanced_slots = vec![false; deck.cards.len()];
+    for enhanced in deck_response.data.bonus_icons {
+        let slot = (0..deck.cards.len())
+            .find(|&slot| !enhanced_slots[slot] && deck.cards[slot] == enhanced.card_id);
+        match slot {
+            Some(slot) => {
+                enhanced_slots[slot] = true;
+                deck.enhance(slot, enhanced.bonus_icons);
+            }
+            None => warn!("Enhanced card {} isn't in the deck that many times", enhanced.card_id),
+        }
+    }
+    if deck.houses.len() != 3 {
+        error!("Deck {} has {} houses, expected 3", deck_response.data.name, deck.houses.len());
+        return Err(DeckError);
+    }
     Ok(deck)
 }
 
 #[cfg(test)]
 pub mod test {
-    use game::CardDetails;
+    use board::DeckBoard;
+    use game::test::test_card;
+    use game::{CardDetails, Flank, House, Type};
+    use player::test::TestPlayer;
     use super::Deck;
+    use turn::Turn;
 
     pub fn deck_from(card_details: CardDetails, count: u32) -> Deck {
         let mut deck = Deck::new();
         let id = card_details.id.clone();
-        let house = card_details.house;
+        let house = card_details.house.clone();
         deck.card_details.insert(card_details.id.clone(), card_details);
         for _ in 0..count {
             deck.cards.push(id.clone());
@@ -114,4 +211,87 @@ pub mod test {
         deck.houses.push(house);
         deck
     }
+
+    // A Brobnar turn, for the players to decide in, with a ready 2 power
+    // creature on each side of the table
+    pub fn with_turn<F>(tf: F) where F: Fn(&Turn) {
+        let deck = deck_from(test_card(House::Brobnar, Type::Creature, 0, 2, 0), 36);
+        let mut mine = DeckBoard::new(&deck);
+        let mut opponent = DeckBoard::new(&deck);
+        let card = mine.hand[0];
+        mine.play(card, true, Flank::Right);
+        mine.my_turn_over();
+        let card = opponent.hand[0];
+        opponent.play(card, true, Flank::Right);
+        let turn = Turn::resume(mine, opponent, House::Brobnar,
+                                Box::new(TestPlayer { house: House::Brobnar }));
+        tf(&turn);
+    }
+
+    #[test]
+    fn test_card_metadata() {
+        use game::{BonusIcon, House, Rarity};
+        use serde_json;
+        use super::{deck_from_response, DeckResponse};
+
+        let card = |id: &str, house: &str, extra: &str| format!(
+            r#"{{"id": "{}", "card_title": "{}", "house": "{}", "card_type": "Creature", "front_image": "",
+                 "card_text": null, "traits": null, "amber": 0, "power": 3, "armor": 0, "flavor_text": null{}}}"#,
+            id, id, house, extra);
+        let json = format!(
+            r#"{{"data": {{"name": "test", "_links": {{"cards": ["a", "a", "b", "c", "d"],
+                                                       "houses": ["Dis", "Logos", "Shadows"]}},
+                           "bonus_icons": [{{"card_id": "a", "bonus_icons": ["capture", "capture"]}},
+                                           {{"card_id": "a", "bonus_icons": ["damage"]}}]}},
+                "_linked": {{"cards": [{}, {}, {}, {}]}}}}"#,
+            card("a", "Dis", r#", "expansion": 341, "card_number": "7", "rarity": "Rare""#),
+            card("b", "Logos", r#", "expansion": 435, "rarity": "Common", "is_maverick": true"#),
+            card("c", "Shadows", r#", "expansion": 341, "rarity": "Common", "is_enhanced": true,
+                                     "bonus_icons": ["amber", "draw"]"#),
+            card("d", "Shadows", ""));
+        let response: DeckResponse = serde_json::from_str(&json).unwrap();
+        let deck = deck_from_response(response).unwrap();
+        assert_eq!(deck.houses, vec![House::Dis, House::Logos, House::Shadows]);
+        // The two copies of "a" were enhanced differently, and both keep its id
+        assert_eq!(deck.cards[..2], ["a".to_string(), "a".to_string()]);
+        assert_eq!(deck.card(0).enhancements, [BonusIcon::Capture, BonusIcon::Capture]);
+        assert_eq!(deck.card(0).bonus_count(&BonusIcon::Capture), 2);
+        assert_eq!(deck.card(1).enhancements, [BonusIcon::Damage]);
+        assert_eq!(deck.card(1).bonus_count(&BonusIcon::Capture), 0);
+        assert!(deck.card(2).enhancements.is_empty());
+        assert_eq!(deck.card(3).bonus_count(&BonusIcon::Draw), 1);
+
+        let a = &deck.card_details["a"];
+        assert_eq!((a.expansion, a.card_number.as_deref()), (Some(341), Some("7")));
+        assert!(deck.card_details["b"].is_maverick);
+        assert_eq!(deck.card_details["c"].bonus_icons, vec![BonusIcon::Amber, BonusIcon::Draw]);
+        assert_eq!(deck.card_details["d"].rarity, None);
+        assert!(!deck.card_details["d"].is_anomaly);
+
+        assert_eq!(deck.count_by(|card| card.expansion), vec![(Some(341), 3), (Some(435), 1), (None, 1)]);
+        assert_eq!(deck.count_by(|card| card.rarity.clone()),
+                   vec![(Some(Rarity::Rare), 2), (Some(Rarity::Common), 2), (None, 1)]);
+    }
+
+    #[test]
+    fn test_wrong_house_count() {
+        use std::env;
+        use std::fs;
+        use std::process;
+        use super::load_deck_file;
+
+        let card = |id: &str, house: &str| format!(
+            r#"{{"id": "{}", "card_title": "{}", "house": "{}", "card_type": "Action", "front_image": "",
+                 "card_text": null, "traits": null, "amber": 1, "power": 0, "armor": 0, "flavor_text": null}}"#,
+            id, id, house);
+        let json = format!(
+            r#"{{"data": {{"name": "test", "_links": {{"cards": ["a", "b"]}}}},
+                "_linked": {{"cards": [{}, {}]}}}}"#,
+            card("a", "Dis"), card("b", "Logos"));
+        let path = env::temp_dir().join(format!("two-houses-{}.deck", process::id()));
+        fs::write(&path, json).unwrap();
+        let result = load_deck_file(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
 }
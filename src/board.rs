@@ -1,15 +1,33 @@
 use api::Deck;
-use game::{CardDetails, Type, Flank};
-use rand::{thread_rng, seq::SliceRandom};
+use game::{BonusIcon, Card, Type, Flank};
+use rand::{thread_rng, Rng, SeedableRng, seq::SliceRandom};
+use rand::rngs::StdRng;
 use field::{Artifact, Creature, FieldCard};
+use trigger::Source;
+
+// Out of play zones a card can be put into
+#[derive(Deserialize, Serialize, Debug, PartialEq, Copy, Clone)]
+pub enum Zone {
+    Hand,
+    Discard,
+    Archives,
+    Purged,
+}
+
+// Amber needed to forge a key, and keys needed to win
+pub const KEY_COST: u32 = 6;
+pub const KEYS_TO_WIN: u32 = 3;
 
+#[derive(Clone)]
 pub struct DeckBoard<'a> {
     pub deck_details: &'a Deck,
 
     // Card Reserves
-    pub hand: Vec<&'a CardDetails>,
-    pub deck: Vec<&'a CardDetails>,
-    pub discard: Vec<&'a CardDetails>,
+    pub hand: Vec<Card<'a>>,
+    pub deck: Vec<Card<'a>>,
+    pub discard: Vec<Card<'a>>,
+    pub archives: Vec<Card<'a>>,
+    pub purged: Vec<Card<'a>>,
 
     // Field
     pub creatures: Vec<Creature<'a>>,
@@ -19,15 +37,26 @@ pub struct DeckBoard<'a> {
     pub amber: u32,
     pub keys: u32,
     pub chains: u32,
+
+    // Shuffles the deck, so seeded boards play out the same every time
+    pub rng: StdRng,
+    // What the rng was first seeded with
+    pub seed: u64,
 }
 
 impl<'a> DeckBoard<'a> {
-    pub fn new(deck_details: &Deck) -> DeckBoard {
+    pub fn new(deck_details: &Deck) -> DeckBoard<'_> {
+        DeckBoard::seeded(deck_details, thread_rng().gen())
+    }
+
+    pub fn seeded(deck_details: &Deck, seed: u64) -> DeckBoard<'_> {
         let mut deck = DeckBoard {
             deck_details,
             hand: Vec::new(),
             deck: Vec::new(),
             discard: Vec::new(),
+            archives: Vec::new(),
+            purged: Vec::new(),
 
             creatures: Vec::new(),
             artifacts: Vec::new(),
@@ -35,10 +64,12 @@ impl<'a> DeckBoard<'a> {
             amber: 0,
             keys: 0,
             chains: 0,
+
+            rng: StdRng::seed_from_u64(seed),
+            seed,
         };
-        for id in &deck.deck_details.cards {
-            let details = &deck.deck_details.card_details[id];
-            deck.deck.push(details);
+        for slot in 0..deck_details.cards.len() {
+            deck.deck.push(deck_details.card(slot));
         }
         deck.shuffle();
         deck.draw_to(6);
@@ -46,8 +77,7 @@ impl<'a> DeckBoard<'a> {
     }
 
     pub fn shuffle(&mut self) {
-        let mut rng = thread_rng();
-        self.deck.shuffle(&mut rng);
+        self.deck.shuffle(&mut self.rng);
     }
 
     pub fn reshuffle_discard(&mut self) {
@@ -55,13 +85,16 @@ impl<'a> DeckBoard<'a> {
         self.shuffle();
     }
 
-    pub fn draw_card(&mut self) {
+    // Returns the card drawn, if there was one
+    pub fn draw_card(&mut self) -> Option<Card<'a>> {
         loop {
             match self.deck.pop() {
                 Some(card) => {
                     self.hand.push(card);
-                    return;
+                    return Some(card);
                 }
+                // Nothing left to draw at all
+                None if self.discard.is_empty() => return None,
                 None => self.reshuffle_discard()
             }
         }
@@ -72,34 +105,102 @@ impl<'a> DeckBoard<'a> {
         self.discard.push(out);
     }
 
-    pub fn draw_to(&mut self, limit: usize) {
+    pub fn put_in(&mut self, zone: Zone, card: Card<'a>) {
+        match zone {
+            Zone::Hand => self.hand.push(card),
+            Zone::Discard => self.discard.push(card),
+            Zone::Archives => self.archives.push(card),
+            Zone::Purged => self.purged.push(card),
+        }
+    }
+
+    // Shuffles the hand back into the deck and draws one card fewer.
+    // Returns the new hand.
+    pub fn mulligan(&mut self) -> Vec<Card<'a>> {
+        let size = self.hand.len();
+        self.deck.append(&mut self.hand);
+        self.shuffle();
+        self.draw_to(size.saturating_sub(1))
+    }
+
+    // Returns the cards drawn
+    pub fn draw_to(&mut self, limit: usize) -> Vec<Card<'a>> {
+        let mut drawn = Vec::new();
         while self.hand.len() < limit {
-            self.draw_card();
+            match self.draw_card() {
+                Some(card) => drawn.push(card),
+                None => break,
+            }
+        }
+        drawn
+    }
+
+    // Forges a key if there is enough amber, returns whether one was forged
+    pub fn forge_key(&mut self) -> bool {
+        if self.amber >= KEY_COST {
+            self.amber -= KEY_COST;
+            self.keys += 1;
+            true
+        } else {
+            false
         }
     }
 
     // In this function, we assume that the passed card is playable.
     // house choice and other effects should be tested before calling it
-    pub fn play(&mut self, cd: &'a CardDetails, from_hand: bool, flank: Flank) {
+    // Returns where the card ended up if it stayed in play
+    pub fn play(&mut self, cd: Card<'a>, from_hand: bool, flank: Flank) -> Option<Source> {
         // Increment any immediate amber
         self.amber += cd.amber;
 
         if from_hand {
             // Remove the card from your hand if you played it from there
-            self.hand.remove_item(&cd);
+            if let Some(index) = self.hand.iter().position(|&card| card == cd) {
+                self.hand.remove(index);
+            }
+        }
+
+        // Bonus icons resolve before the card does. Capture and damage icons
+        // need the opponent, so the turn resolves those.
+        self.amber += cd.bonus_count(&BonusIcon::Amber);
+        for _ in 0..cd.bonus_count(&BonusIcon::Draw) {
+            self.draw_card();
         }
 
         match cd.card_type {
-            Type::Action => self.discard.push(cd),
+            Type::Action => {
+                self.discard.push(cd);
+                None
+            }
             Type::Artifact => {
-                self.artifacts.push(Artifact::new(cd))
+                let mut artifact = Artifact::new(cd);
+                artifact.on_enter_play();
+                self.artifacts.push(artifact);
+                Some(Source::Artifact(self.artifacts.len() - 1))
+            }
+            Type::Upgrade => {
+                error!("Upgrades not yet implemented");
+                None
+            }
+            ref card_type if card_type.is_creature() => Some(Source::Creature(self.play_creature(cd, flank))),
+            ref card_type => {
+                error!("{} cards not yet implemented", card_type);
+                None
             }
-            Type::Upgrade => error!("Upgrades not yet implemented"),
-            Type::Creature => self.play_creature(cd, flank),
         }
     }
 
-    fn play_creature(&mut self, cd: &'a CardDetails, flank: Flank) {
+    // Returns the index the creature was placed at
+    fn play_creature(&mut self, cd: Card<'a>, flank: Flank) -> usize {
+        let mut creature = Creature::new(cd);
+        creature.on_enter_play();
+        self.place_creature(creature, flank)
+    }
+
+    // Puts a creature on a flank of this board as it is, without any
+    // enter play effects. Returns the index the creature was placed at.
+    pub fn place_creature(&mut self, mut creature: Creature<'a>, flank: Flank) -> usize {
+        creature.on_flank = true;
         match flank {
             Flank::Left => {
                 if self.creatures.len() > 1 {
@@ -107,7 +208,8 @@ impl<'a> DeckBoard<'a> {
                         current_flank.on_flank = false;
                     }
                 }
-                self.creatures.insert(0, Creature::new(cd));
+                self.creatures.insert(0, creature);
+                0
             }
             Flank::Right => {
                 if self.creatures.len() > 1 {
@@ -115,16 +217,16 @@ impl<'a> DeckBoard<'a> {
                         current_flank.on_flank = false;
                     }
                 }
-                self.creatures.push(Creature::new(cd));
+                self.creatures.push(creature);
+                self.creatures.len() - 1
             }
         }
     }
 
-    // Any destroyed effects should happen before this
-    // By default, the creature is just purged, the card needs to be manually
-    // Added to the discard
-    pub fn destroy_creature(&mut self, index: usize) -> &'a CardDetails {
-        let destroyed_creature = self.creatures.remove(index);
+    // Takes a creature off this board as it is, keeping its damage, amber
+    // and upgrades
+    pub fn remove_creature(&mut self, index: usize) -> Creature<'a> {
+        let creature = self.creatures.remove(index);
         if let Some(front) = self.creatures.first_mut() {
             front.on_flank = true;
         }
@@ -132,9 +234,28 @@ impl<'a> DeckBoard<'a> {
         if let Some(back) = self.creatures.last_mut() {
             back.on_flank = true;
         }
+        creature
+    }
+
+    // Any destroyed effects should happen before this, see Turn::destroy
+    // By default, the creature is just purged, the card needs to be manually
+    // Added to the discard
+    pub fn destroy_creature(&mut self, index: usize) -> Card<'a> {
+        let mut destroyed_creature = self.remove_creature(index);
+        destroyed_creature.on_leave_play();
         destroyed_creature.details
     }
 
+    pub fn start_of_turn(&mut self) {
+        for c in &mut self.creatures {
+            c.on_start_of_turn();
+        }
+
+        for a in &mut self.artifacts {
+            a.on_start_of_turn();
+        }
+    }
+
     pub fn my_turn_over(&mut self) {
         for c in &mut self.creatures {
             c.on_my_turn_over();
@@ -217,6 +338,23 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_copies_are_tracked() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        deck_fixture(card, |f| {
+            let mut slots: Vec<usize> = f.deck_board.hand.iter().chain(&f.deck_board.deck).map(|c| c.slot).collect();
+            slots.sort();
+            assert_eq!(slots, (0..36).collect::<Vec<_>>());
+
+            // Playing a copy takes that one out of hand, not just the first match
+            let played = f.deck_board.hand[3];
+            f.deck_board.play(played, true, Flank::Left);
+            assert!(!f.deck_board.hand.contains(&played));
+            assert_eq!(f.deck_board.hand.len(), 5);
+            assert_eq!(f.deck_board.creatures[0].details, played);
+        });
+    }
+
     #[test]
     fn test_play_creature() {
         let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
@@ -232,9 +370,9 @@ mod test {
             f.deck_board.play(card, true, Flank::Left);
             assert_eq!(f.deck_board.creatures.len(), 3);
 
-            assert_eq!(f.deck_board.creatures[0].on_flank, true);
-            assert_eq!(f.deck_board.creatures[1].on_flank, false);
-            assert_eq!(f.deck_board.creatures[2].on_flank, true);
+            assert!(f.deck_board.creatures[0].on_flank);
+            assert!(!f.deck_board.creatures[1].on_flank);
+            assert!(f.deck_board.creatures[2].on_flank);
         });
     }
 
@@ -246,13 +384,36 @@ mod test {
             f.deck_board.play(card, true, Flank::Left);
             f.deck_board.play(card, true, Flank::Left);
             f.deck_board.play(card, true, Flank::Left);
-            assert_eq!(f.deck_board.creatures[0].on_flank, true);
-            assert_eq!(f.deck_board.creatures[1].on_flank, false);
-            assert_eq!(f.deck_board.creatures[2].on_flank, true);
+            assert!(f.deck_board.creatures[0].on_flank);
+            assert!(!f.deck_board.creatures[1].on_flank);
+            assert!(f.deck_board.creatures[2].on_flank);
             f.deck_board.destroy_creature(0);
             assert_eq!(f.deck_board.creatures.len(), 2);
-            assert_eq!(f.deck_board.creatures[0].on_flank, true);
-            assert_eq!(f.deck_board.creatures[1].on_flank, true);
+            assert!(f.deck_board.creatures[0].on_flank);
+            assert!(f.deck_board.creatures[1].on_flank);
+        });
+    }
+
+    #[test]
+    fn test_draw_empty() {
+        fixture(|f| {
+            let deck_len = f.deck_board.deck.len();
+            f.deck_board.draw_to(deck_len + 10);
+            assert_eq!(f.deck_board.hand.len(), deck_len + 6);
+            f.deck_board.draw_card();
+            assert_eq!(f.deck_board.hand.len(), deck_len + 6);
+        });
+    }
+
+    #[test]
+    fn test_forge_key() {
+        fixture(|f| {
+            f.deck_board.amber = 5;
+            assert!(!f.deck_board.forge_key());
+            f.deck_board.amber = 7;
+            assert!(f.deck_board.forge_key());
+            assert_eq!(f.deck_board.amber, 1);
+            assert_eq!(f.deck_board.keys, 1);
         });
     }
 }
@@ -1,37 +1,95 @@
-use game::CardDetails;
+use game::Card;
 use std::cmp::min;
+use trigger::Ability;
+use board::Zone;
+
+// Who owns a card in play, relative to the player controlling it
+#[derive(Deserialize, Serialize, Debug, PartialEq, Copy, Clone)]
+pub enum Owner {
+    Controller,
+    Opponent,
+}
+
+impl Owner {
+    pub fn other(self) -> Owner {
+        match self {
+            Owner::Controller => Owner::Opponent,
+            Owner::Opponent => Owner::Controller,
+        }
+    }
+}
 
 pub trait FieldCard {
     fn on_turn_over(&mut self);
     fn on_my_turn_over(&mut self);
+    // Abilities the turn listens for while this card is in play
+    fn abilities(&self) -> &[Ability];
+    // Called when the card moves to the other player's side of the table
+    fn on_control_changed(&mut self);
+
+    // Lifecycle hooks, called by the board and turn as things happen to the card
+    fn on_enter_play(&mut self) {}
+    fn on_leave_play(&mut self) {}
+    fn on_start_of_turn(&mut self) {}
+    fn on_before_fight(&mut self) {}
+    fn on_after_fight(&mut self) {}
+    fn on_reaped(&mut self) {}
+    fn on_damaged(&mut self, _amount: u32) {}
+    fn on_destroyed(&mut self) {}
 }
 
 #[derive(Clone)]
 pub struct Creature<'a> {
-    pub details: &'a CardDetails,
+    pub details: Card<'a>,
+    pub owner: Owner,
     pub on_flank: bool,
     pub damage: u32,
     pub armor: u32,
     pub stunned: bool,
     pub exhausted: bool,
+    pub warded: bool,
+    pub elusive: bool,
+    pub skirmish: bool,
+    // Whether this creature has been attacked this turn, for elusive
+    pub attacked: bool,
+    // Whether it fought this turn, attacking or defending, and the damage
+    // it took this turn after armor
+    pub fought: bool,
+    pub damage_taken: u32,
+    // Destroyed, and only still in play until its "Destroyed:" abilities resolve
+    pub destroyed: bool,
+    // Amber captured onto this creature
+    pub captured: u32,
+    pub upgrades: Vec<Upgrade<'a>>,
+    // Where the card goes when it is destroyed
+    pub leaves_to: Zone,
+    pub abilities: Vec<Ability>,
 }
 
 impl<'a> Creature<'a> {
-    pub fn new(details: &'a CardDetails) -> Self {
+    pub fn new(details: Card<'a>) -> Self {
         Creature {
             details,
+            owner: Owner::Controller,
             on_flank: true,
             damage: 0,
             armor: details.armor,
             stunned: false,
             exhausted: true,
+            warded: false,
+            elusive: details.has_keyword("Elusive"),
+            skirmish: details.has_keyword("Skirmish"),
+            attacked: false,
+            fought: false,
+            damage_taken: 0,
+            destroyed: false,
+            captured: 0,
+            upgrades: Vec::new(),
+            leaves_to: Zone::Discard,
+            abilities: Vec::new(),
         }
     }
 
-    pub fn max_damage(&self) -> u32 {
-        self.details.power
-    }
-
     pub fn max_armor(&self) -> u32 {
         self.details.armor
     }
@@ -40,43 +98,125 @@ impl<'a> Creature<'a> {
         self.details.power
     }
 
+    pub fn max_damage(&self) -> u32 {
+        self.details.power
+    }
+
     pub fn do_damage(&mut self, mut damage: u32) {
+        if damage > 0 && self.warded {
+            // Ward prevents the damage and is used up
+            self.warded = false;
+            return;
+        }
         let armor_damage = min(self.armor, damage);
         self.armor -= armor_damage;
         damage -= armor_damage;
         self.damage += damage;
+        if damage > 0 {
+            self.on_damaged(damage);
+        }
     }
 
-    pub fn fight<'b>(&mut self, other: &'b mut Creature) {
-        self.do_damage(other.power());
+    pub fn fight(&mut self, other: &mut Creature) {
+        // The first time an elusive creature is attacked each turn, no damage is dealt
+        let evaded = other.elusive && !other.attacked;
+        other.attacked = true;
+        if evaded {
+            return;
+        }
+        if !self.skirmish {
+            self.do_damage(other.power());
+        }
         other.do_damage(self.power());
     }
 
     pub fn is_alive(&self) -> bool {
-        self.damage < self.power()
+        self.damage < self.max_damage()
     }
 }
 
 impl<'a> FieldCard for Creature<'a> {
     fn on_turn_over(&mut self) {
         self.armor = self.max_armor();
+        self.attacked = false;
     }
 
     fn on_my_turn_over(&mut self) {
         self.exhausted = false;
     }
+
+    fn abilities(&self) -> &[Ability] {
+        &self.abilities
+    }
+
+    // Upgrades stay on the creature but keep their own owner
+    fn on_control_changed(&mut self) {
+        self.owner = self.owner.other();
+        for upgrade in &mut self.upgrades {
+            upgrade.owner = upgrade.owner.other();
+        }
+    }
+
+    fn on_enter_play(&mut self) {
+        self.exhausted = true;
+    }
+
+    // Cards lose all damage, armor loss and statuses when they leave play
+    fn on_leave_play(&mut self) {
+        self.damage = 0;
+        self.armor = self.max_armor();
+        self.stunned = false;
+        self.exhausted = true;
+    }
+
+    fn on_start_of_turn(&mut self) {
+        self.fought = false;
+        self.damage_taken = 0;
+    }
+
+    fn on_before_fight(&mut self) {
+        self.exhausted = true;
+    }
+
+    fn on_after_fight(&mut self) {
+        self.fought = true;
+    }
+
+    fn on_reaped(&mut self) {
+        self.exhausted = true;
+    }
+
+    fn on_damaged(&mut self, amount: u32) {
+        self.damage_taken += amount;
+    }
+
+    fn on_destroyed(&mut self) {
+        self.destroyed = true;
+    }
 }
 
+// A card attached to a creature. Its owner can differ from the creature's.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Upgrade<'a> {
+    pub card: Card<'a>,
+    pub owner: Owner,
+}
+
+#[derive(Clone)]
 pub struct Artifact<'a> {
-    details: &'a CardDetails,
-    exhausted: bool,
+    pub details: Card<'a>,
+    pub exhausted: bool,
+    pub owner: Owner,
+    pub abilities: Vec<Ability>,
 }
 
 impl<'a> Artifact<'a> {
-    pub fn new(details: &'a CardDetails) -> Self {
+    pub fn new(details: Card<'a>) -> Self {
         Artifact {
             details,
             exhausted: true,
+            owner: Owner::Controller,
+            abilities: Vec::new(),
         }
     }
 }
@@ -87,12 +227,28 @@ impl<'a> FieldCard for Artifact<'a> {
     fn on_my_turn_over(&mut self) {
         self.exhausted = false;
     }
+
+    fn abilities(&self) -> &[Ability] {
+        &self.abilities
+    }
+
+    fn on_control_changed(&mut self) {
+        self.owner = self.owner.other();
+    }
+
+    fn on_enter_play(&mut self) {
+        self.exhausted = true;
+    }
+
+    fn on_leave_play(&mut self) {
+        self.exhausted = true;
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use game::{CardDetails, House, Type, test::test_card};
-    use field::{Creature, Artifact, FieldCard};
+    use game::{Card, House, Type, test::test_card};
+    use field::{Creature, FieldCard};
 
     struct TestFixture<'a> {
         pub creature: Creature<'a>
@@ -104,19 +260,19 @@ mod test {
             Type::Creature,
             0, 2, 2,
         );
-        let mut o = TestFixture { creature: Creature::new(&card_details) };
+        let mut o = TestFixture { creature: Creature::new(Card::new(0, &card_details)) };
         tf(&mut o);
     }
 
     #[test]
     fn test_creature_reset() {
         creature_fixture(|f| {
-            assert_eq!(f.creature.exhausted, true);
+            assert!(f.creature.exhausted);
             assert_eq!(f.creature.armor, 2);
             f.creature.on_turn_over();
-            assert_eq!(f.creature.exhausted, true);
+            assert!(f.creature.exhausted);
             f.creature.on_my_turn_over();
-            assert_eq!(f.creature.exhausted, false);
+            assert!(!f.creature.exhausted);
             assert_eq!(f.creature.max_armor(), 2);
             f.creature.armor = 0;
             f.creature.on_turn_over();
@@ -138,4 +294,33 @@ mod test {
             assert!(!f.creature.is_alive());
         });
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_creature_ward() {
+        creature_fixture(|f| {
+            f.creature.warded = true;
+            f.creature.do_damage(0);
+            assert!(f.creature.warded);
+            f.creature.do_damage(5);
+            assert!(!f.creature.warded);
+            assert_eq!(f.creature.armor, 2);
+            assert_eq!(f.creature.damage, 0);
+            f.creature.do_damage(5);
+            assert!(!f.creature.is_alive());
+        });
+    }
+
+    #[test]
+    fn test_creature_leave_play() {
+        creature_fixture(|f| {
+            f.creature.on_my_turn_over();
+            f.creature.do_damage(3);
+            f.creature.stunned = true;
+            f.creature.on_leave_play();
+            assert_eq!(f.creature.damage, 0);
+            assert_eq!(f.creature.armor, 2);
+            assert!(!f.creature.stunned);
+            assert!(f.creature.exhausted);
+        });
+    }
+}
@@ -1,6 +1,11 @@
 use std::cmp::PartialEq;
+use std::fmt;
+use std::ops::Deref;
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Copy, Clone)]
+// Houses and types are named as the deck API spells them. Ones from sets
+// newer than this list load as Unknown, so the deck can still be read.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[serde(from = "String", into = "String")]
 pub enum House {
     Brobnar,
     Dis,
@@ -9,16 +14,224 @@ pub enum House {
     Sanctum,
     Shadows,
     Untamed,
+    StarAlliance,
+    Saurian,
+    Ekwidon,
+    Geistoid,
+    Unfathomable,
+    Unknown(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Copy, Clone)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[serde(from = "String", into = "String")]
 pub enum Type {
     Action,
     Artifact,
     Creature,
     Upgrade,
+    TokenCreature,
+    // The two cards a gigantic creature is printed on
+    GiganticTop,
+    GiganticBottom,
+    Unknown(String),
+}
+
+impl House {
+    pub fn name(&self) -> &str {
+        match *self {
+            House::Brobnar => "Brobnar",
+            House::Dis => "Dis",
+            House::Logos => "Logos",
+            House::Mars => "Mars",
+            House::Sanctum => "Sanctum",
+            House::Shadows => "Shadows",
+            House::Untamed => "Untamed",
+            House::StarAlliance => "Star Alliance",
+            House::Saurian => "Saurian",
+            House::Ekwidon => "Ekwidon",
+            House::Geistoid => "Geistoid",
+            House::Unfathomable => "Unfathomable",
+            House::Unknown(ref name) => name,
+        }
+    }
+}
+
+impl From<String> for House {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "Brobnar" => House::Brobnar,
+            "Dis" => House::Dis,
+            "Logos" => House::Logos,
+            "Mars" => House::Mars,
+            "Sanctum" => House::Sanctum,
+            "Shadows" => House::Shadows,
+            "Untamed" => House::Untamed,
+            "Star Alliance" | "StarAlliance" => House::StarAlliance,
+            "Saurian" => House::Saurian,
+            "Ekwidon" => House::Ekwidon,
+            "Geistoid" => House::Geistoid,
+            "Unfathomable" => House::Unfathomable,
+            _ => House::Unknown(name),
+        }
+    }
+}
+
+impl From<House> for String {
+    fn from(house: House) -> Self {
+        house.name().to_string()
+    }
+}
+
+impl fmt::Display for House {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl Type {
+    pub fn name(&self) -> &str {
+        match *self {
+            Type::Action => "Action",
+            Type::Artifact => "Artifact",
+            Type::Creature => "Creature",
+            Type::Upgrade => "Upgrade",
+            Type::TokenCreature => "Token Creature",
+            Type::GiganticTop => "Creature1",
+            Type::GiganticBottom => "Creature2",
+            Type::Unknown(ref name) => name,
+        }
+    }
+
+    // Whether the card goes into play as a creature
+    pub fn is_creature(&self) -> bool {
+        matches!(*self, Type::Creature | Type::TokenCreature | Type::GiganticTop | Type::GiganticBottom)
+    }
+}
+
+impl From<String> for Type {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "Action" => Type::Action,
+            "Artifact" => Type::Artifact,
+            "Creature" => Type::Creature,
+            "Upgrade" => Type::Upgrade,
+            "Token Creature" | "TokenCreature" => Type::TokenCreature,
+            "Creature1" | "GiganticTop" => Type::GiganticTop,
+            "Creature2" | "GiganticBottom" => Type::GiganticBottom,
+            _ => Type::Unknown(name),
+        }
+    }
+}
+
+impl From<Type> for String {
+    fn from(card_type: Type) -> Self {
+        card_type.name().to_string()
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[serde(from = "String", into = "String")]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Special,
+    Fixed,
+    Variant,
+    Unknown(String),
+}
+
+impl Rarity {
+    pub fn name(&self) -> &str {
+        match *self {
+            Rarity::Common => "Common",
+            Rarity::Uncommon => "Uncommon",
+            Rarity::Rare => "Rare",
+            Rarity::Special => "Special",
+            Rarity::Fixed => "FIXED",
+            Rarity::Variant => "Variant",
+            Rarity::Unknown(ref name) => name,
+        }
+    }
+}
+
+impl From<String> for Rarity {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "Common" => Rarity::Common,
+            "Uncommon" => Rarity::Uncommon,
+            "Rare" => Rarity::Rare,
+            "Special" => Rarity::Special,
+            "FIXED" => Rarity::Fixed,
+            "Variant" => Rarity::Variant,
+            _ => Rarity::Unknown(name),
+        }
+    }
+}
+
+impl From<Rarity> for String {
+    fn from(rarity: Rarity) -> Self {
+        rarity.name().to_string()
+    }
+}
+
+impl fmt::Display for Rarity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+// The icons printed above a card's text, each resolved when it is played
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[serde(from = "String", into = "String")]
+pub enum BonusIcon {
+    Amber,
+    Capture,
+    Damage,
+    Draw,
+    Discard,
+    Unknown(String),
+}
+
+impl BonusIcon {
+    pub fn name(&self) -> &str {
+        match *self {
+            BonusIcon::Amber => "amber",
+            BonusIcon::Capture => "capture",
+            BonusIcon::Damage => "damage",
+            BonusIcon::Draw => "draw",
+            BonusIcon::Discard => "discard",
+            BonusIcon::Unknown(ref name) => name,
+        }
+    }
 }
 
+impl From<String> for BonusIcon {
+    fn from(name: String) -> Self {
+        match name.to_lowercase().as_str() {
+            "amber" | "aember" => BonusIcon::Amber,
+            "capture" => BonusIcon::Capture,
+            "damage" => BonusIcon::Damage,
+            "draw" => BonusIcon::Draw,
+            "discard" => BonusIcon::Discard,
+            _ => BonusIcon::Unknown(name),
+        }
+    }
+}
+
+impl From<BonusIcon> for String {
+    fn from(icon: BonusIcon) -> Self {
+        icon.name().to_string()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Copy, Clone)]
 pub enum Flank {
     Left,
     Right,
@@ -37,6 +250,35 @@ pub struct CardDetails {
     pub power: u32,
     pub armor: u32,
     pub flavor_text: Option<String>,
+    // The rest is printing metadata, missing from decks saved before it was
+    // kept. A maverick's house is the one it was moved to.
+    #[serde(default)]
+    pub expansion: Option<u32>,
+    #[serde(default)]
+    pub card_number: Option<String>,
+    #[serde(default)]
+    pub rarity: Option<Rarity>,
+    #[serde(default)]
+    pub is_maverick: bool,
+    #[serde(default)]
+    pub is_anomaly: bool,
+    #[serde(default)]
+    pub is_enhanced: bool,
+    #[serde(default)]
+    pub bonus_icons: Vec<BonusIcon>,
+}
+
+impl CardDetails {
+    // Whether the card text lists the keyword, e.g. "Elusive. Skirmish."
+    pub fn has_keyword(&self, keyword: &str) -> bool {
+        match self.card_text {
+            Some(ref text) => text
+                .split(['\u{b}', '\n'])
+                .flat_map(|line| line.split('.'))
+                .any(|part| part.trim() == keyword),
+            None => false,
+        }
+    }
 }
 
 impl PartialEq for CardDetails {
@@ -45,9 +287,49 @@ impl PartialEq for CardDetails {
     }
 }
 
+// One physical copy of a card, wherever it is. `slot` is its index in the
+// card list of the deck it was dealt from, so copies of the same card can be
+// told apart.
+#[derive(Debug, Copy, Clone)]
+pub struct Card<'a> {
+    pub slot: usize,
+    pub details: &'a CardDetails,
+    // Bonus icons this copy was enhanced with, on top of the printed ones
+    pub enhancements: &'a [BonusIcon],
+}
+
+impl<'a> Card<'a> {
+    pub fn new(slot: usize, details: &'a CardDetails) -> Self {
+        Card { slot, details, enhancements: &[] }
+    }
+
+    // How many of this copy's bonus icons, printed or enhanced, are `icon`
+    pub fn bonus_count(&self, icon: &BonusIcon) -> u32 {
+        self.details.bonus_icons.iter()
+            .chain(self.enhancements)
+            .filter(|&i| i == icon)
+            .count() as u32
+    }
+}
+
+impl<'a> PartialEq for Card<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.slot == other.slot && self.details == other.details
+    }
+}
+
+impl<'a> Deref for Card<'a> {
+    type Target = CardDetails;
+
+    fn deref(&self) -> &CardDetails {
+        self.details
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use super::{CardDetails, House, Type};
+    use serde_json;
     use uuid::Uuid;
 
     pub fn test_card(house: House, card_type: Type, amber: u32, power: u32, armor: u32) -> CardDetails {
@@ -63,6 +345,39 @@ pub mod test {
             power,
             armor,
             flavor_text: None,
+            expansion: None,
+            card_number: None,
+            rarity: None,
+            is_maverick: false,
+            is_anomaly: false,
+            is_enhanced: false,
+            bonus_icons: Vec::new(),
         }
     }
+
+    #[test]
+    fn test_has_keyword() {
+        let mut card = test_card(House::Untamed, Type::Creature, 0, 2, 0);
+        assert!(!card.has_keyword("Elusive"));
+        card.card_text = Some("Elusive. Skirmish.\u{b}Reap: Deal 2<D> to a creature.".to_string());
+        assert!(card.has_keyword("Elusive"));
+        assert!(card.has_keyword("Skirmish"));
+        card.card_text = Some("While Niffle Ape is attacking, ignore taunt and elusive.".to_string());
+        assert!(!card.has_keyword("Elusive"));
+    }
+
+    #[test]
+    fn test_newer_houses_and_types() {
+        let houses: Vec<House> = serde_json::from_str(r#"["Dis", "Star Alliance", "Ekwidon", "Keyraken"]"#).unwrap();
+        assert_eq!(houses, vec![House::Dis, House::StarAlliance, House::Ekwidon,
+                                House::Unknown("Keyraken".to_string())]);
+        assert_eq!(serde_json::to_string(&houses).unwrap(), r#"["Dis","Star Alliance","Ekwidon","Keyraken"]"#);
+        assert_eq!(House::StarAlliance.to_string(), "Star Alliance");
+
+        let types: Vec<Type> = serde_json::from_str(r#"["Creature", "Token Creature", "Creature2", "Plot"]"#).unwrap();
+        assert_eq!(types, vec![Type::Creature, Type::TokenCreature, Type::GiganticBottom,
+                               Type::Unknown("Plot".to_string())]);
+        assert!(types[1].is_creature() && types[2].is_creature());
+        assert!(!types[3].is_creature());
+    }
 }
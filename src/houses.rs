@@ -0,0 +1,167 @@
+// Which houses a deck's player calls over a series, and how good its opening
+// hands are.
+
+use game::House;
+use replay::LogEvent;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct HouseRecord {
+    pub house: House,
+    // Turns the house was chosen
+    pub picks: u32,
+    // Games in which it was chosen on the first turn, and wins of those
+    pub first_games: u32,
+    pub first_wins: u32,
+}
+
+// House choices and opening hands for one side of a series
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct HouseStats {
+    pub games: u32,
+    // In the order the houses were first seen
+    pub houses: Vec<HouseRecord>,
+    // Opening hands by the most cards they held of any one house, which is
+    // how many cards the best first turn could play
+    pub opening_hands: Vec<u32>,
+}
+
+fn rate(count: u32, out_of: u32) -> f64 {
+    if out_of == 0 {
+        0.0
+    } else {
+        f64::from(count) / f64::from(out_of)
+    }
+}
+
+// Cards held of the best represented house
+pub fn best_house_count(hand: &[House]) -> usize {
+    hand.iter().map(|house| hand.iter().filter(|&h| h == house).count()).max().unwrap_or(0)
+}
+
+impl HouseStats {
+    fn house(&mut self, house: &House) -> &mut HouseRecord {
+        match self.houses.iter().position(|record| record.house == *house) {
+            Some(index) => &mut self.houses[index],
+            None => {
+                self.houses.push(HouseRecord { house: house.clone(), picks: 0, first_games: 0, first_wins: 0 });
+                self.houses.last_mut().unwrap()
+            }
+        }
+    }
+
+    // Adds one game, seen from `seat`. `opening_hand` is the houses of the
+    // cards held when the first turn began.
+    pub fn record(&mut self, events: &[LogEvent], seat: usize, won: bool, opening_hand: &[House]) {
+        self.games += 1;
+        let mut first = true;
+        for event in events {
+            if let LogEvent::ChooseHouse { seat: s, ref house } = *event {
+                if s != seat {
+                    continue;
+                }
+                let record = self.house(house);
+                record.picks += 1;
+                if first {
+                    record.first_games += 1;
+                    if won {
+                        record.first_wins += 1;
+                    }
+                    first = false;
+                }
+            }
+        }
+        let best = best_house_count(opening_hand);
+        if self.opening_hands.len() <= best {
+            self.opening_hands.resize(best + 1, 0);
+        }
+        self.opening_hands[best] += 1;
+    }
+
+    pub fn merge(&mut self, other: &HouseStats) {
+        self.games += other.games;
+        for record in &other.houses {
+            let mine = self.house(&record.house);
+            mine.picks += record.picks;
+            mine.first_games += record.first_games;
+            mine.first_wins += record.first_wins;
+        }
+        if self.opening_hands.len() < other.opening_hands.len() {
+            self.opening_hands.resize(other.opening_hands.len(), 0);
+        }
+        for (count, &hands) in other.opening_hands.iter().enumerate() {
+            self.opening_hands[count] += hands;
+        }
+    }
+
+    pub fn pick_rate(&self, house: &House) -> f64 {
+        let total = self.houses.iter().map(|record| record.picks).sum();
+        let picks = self.houses.iter().find(|record| record.house == *house).map_or(0, |record| record.picks);
+        rate(picks, total)
+    }
+
+    pub fn first_choice_win_rate(&self, house: &House) -> f64 {
+        self.houses.iter()
+            .find(|record| record.house == *house)
+            .map_or(0.0, |record| rate(record.first_wins, record.first_games))
+    }
+
+    // Chance of an opening hand with at least `count` cards of one house
+    pub fn opening_hand_rate(&self, count: usize) -> f64 {
+        rate(self.opening_hands.iter().skip(count).sum(), self.games)
+    }
+}
+
+impl fmt::Display for HouseStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<10} {:>7} {:>7} {:>12} {:>10}", "House", "Picks", "Share", "First turn", "Won")?;
+        for record in &self.houses {
+            writeln!(f, "{:<10} {:>7} {:>6.1}% {:>12} {:>9.1}%",
+                     record.house, record.picks, 100.0 * self.pick_rate(&record.house),
+                     record.first_games, 100.0 * self.first_choice_win_rate(&record.house))?;
+        }
+        writeln!(f, "\nOpening hands by cards of their best house:")?;
+        writeln!(f, "{:>5} {:>7} {:>7} {:>9}", "Cards", "Hands", "Share", "At least")?;
+        for (count, &hands) in self.opening_hands.iter().enumerate().skip_while(|&(_, &hands)| hands == 0) {
+            writeln!(f, "{:>5} {:>7} {:>6.1}% {:>8.1}%", count, hands, 100.0 * rate(hands, self.games),
+                     100.0 * self.opening_hand_rate(count))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{best_house_count, HouseStats};
+    use game::House;
+    use replay::LogEvent;
+
+    #[test]
+    fn test_house_stats() {
+        let choose = |seat, house| LogEvent::ChooseHouse { seat, house };
+        let game = vec![choose(0, House::Dis), choose(1, House::Mars), choose(0, House::Logos), choose(0, House::Dis)];
+        let hand = vec![House::Dis, House::Dis, House::Logos, House::Logos, House::Logos, House::Brobnar];
+        assert_eq!(best_house_count(&hand), 3);
+
+        let mut stats = HouseStats::default();
+        stats.record(&game, 0, true, &hand);
+        stats.record(&game[2..], 0, false, &hand[..5]);
+        assert_eq!(stats.houses[0].picks, 3);
+        assert_eq!(stats.houses[1].first_games, 1);
+        assert_eq!(stats.pick_rate(&House::Dis), 0.6);
+        assert_eq!(stats.first_choice_win_rate(&House::Dis), 1.0);
+        assert_eq!(stats.first_choice_win_rate(&House::Logos), 0.0);
+        assert_eq!(stats.opening_hands, vec![0, 0, 0, 2]);
+        assert_eq!(stats.opening_hand_rate(3), 1.0);
+        assert_eq!(stats.opening_hand_rate(4), 0.0);
+
+        let mut other = HouseStats::default();
+        other.record(&game, 1, false, &vec![House::Mars; 6]);
+        other.merge(&stats);
+        assert_eq!(other.games, 3);
+        assert_eq!(other.houses[0].house, House::Mars);
+        assert_eq!(other.opening_hand_rate(3), 1.0);
+        assert_eq!(other.opening_hand_rate(4), 1.0 / 3.0);
+        assert!(other.to_string().contains("Mars"));
+    }
+}
@@ -0,0 +1,268 @@
+// Which cards win games: what each card did over a series, read from the
+// game logs, and how often its side won when it was played compared to when
+// it wasn't.
+
+use api::Deck;
+use replay::LogEvent;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct CardImpact {
+    pub drawn: u32,
+    pub played: u32,
+    // Put into the discard pile, after being played or destroyed
+    pub discarded: u32,
+    pub amber: u32,
+    // Enemy creatures it destroyed in fights
+    pub kills: u32,
+    // Games in which it was played at least once, and how many of those
+    // its side won
+    pub games_played: u32,
+    pub wins_played: u32,
+}
+
+// Card impacts for one side of a series, by card id
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ImpactStats {
+    pub games: u32,
+    pub wins: u32,
+    pub cards: HashMap<String, CardImpact>,
+}
+
+fn rate(count: u32, out_of: u32) -> Option<f64> {
+    if out_of == 0 {
+        None
+    } else {
+        Some(f64::from(count) / f64::from(out_of))
+    }
+}
+
+impl CardImpact {
+    fn merge(&mut self, other: &CardImpact) {
+        self.drawn += other.drawn;
+        self.played += other.played;
+        self.discarded += other.discarded;
+        self.amber += other.amber;
+        self.kills += other.kills;
+        self.games_played += other.games_played;
+        self.wins_played += other.wins_played;
+    }
+}
+
+impl ImpactStats {
+    fn card(&mut self, id: &str) -> &mut CardImpact {
+        self.cards.entry(id.to_string()).or_default()
+    }
+
+    // Adds one game, seen from `seat`
+    pub fn record(&mut self, events: &[LogEvent], seat: usize, won: bool) {
+        self.games += 1;
+        if won {
+            self.wins += 1;
+        }
+        let mut played = HashSet::new();
+        // The opening hand, kept until the mulligan decision is over in case
+        // it goes back into the deck
+        let mut opening_hand = Some(Vec::new());
+        for event in events {
+            match *event {
+                LogEvent::Draw { seat: s, ref card } if s == seat => {
+                    self.card(card).drawn += 1;
+                    if let Some(ref mut hand) = opening_hand {
+                        hand.push(card.clone());
+                    }
+                }
+                // Only the redraw counts
+                LogEvent::Mulligan { seat: s } if s == seat => {
+                    for card in opening_hand.take().unwrap_or_default() {
+                        self.card(&card).drawn -= 1;
+                    }
+                }
+                // The first turn has begun, so the hand was kept
+                LogEvent::ChooseHouse { .. } => opening_hand = None,
+                LogEvent::Play { seat: s, ref card, .. } if s == seat => {
+                    self.card(card).played += 1;
+                    played.insert(card.clone());
+                }
+                LogEvent::Discard { seat: s, ref card } if s == seat => self.card(card).discarded += 1,
+                LogEvent::Amber { seat: s, ref card, amount } if s == seat => self.card(card).amber += amount,
+                // The killer is on the other side from the creature destroyed
+                LogEvent::Destroyed { seat: s, by: Some(ref killer), .. } if s != seat => {
+                    self.card(killer).kills += 1;
+                }
+                _ => {}
+            }
+        }
+        for card in played {
+            let impact = self.card(&card);
+            impact.games_played += 1;
+            if won {
+                impact.wins_played += 1;
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &ImpactStats) {
+        self.games += other.games;
+        self.wins += other.wins;
+        for (id, impact) in &other.cards {
+            self.card(id).merge(impact);
+        }
+    }
+
+    pub fn win_rate_played(&self, id: &str) -> Option<f64> {
+        self.cards.get(id).and_then(|impact| rate(impact.wins_played, impact.games_played))
+    }
+
+    pub fn win_rate_not_played(&self, id: &str) -> Option<f64> {
+        let (wins, games) = self.cards.get(id).map_or((0, 0), |impact| (impact.wins_played, impact.games_played));
+        rate(self.wins - wins, self.games - games)
+    }
+
+    // How much more often the side won with the card played than without
+    pub fn win_rate_gain(&self, id: &str) -> Option<f64> {
+        match (self.win_rate_played(id), self.win_rate_not_played(id)) {
+            (Some(played), Some(not_played)) => Some(played - not_played),
+            _ => None,
+        }
+    }
+
+    // Every card in the deck, best first: by how much playing it raised the
+    // win rate, then by how often it was played
+    pub fn ranked(&self, deck: &Deck) -> Vec<String> {
+        let mut ids: Vec<String> = deck.card_details.keys().cloned().collect();
+        let played = |id: &String| self.cards.get(id).map_or(0, |impact| impact.played);
+        ids.sort_by(|a, b| {
+            let gain = |id| self.win_rate_gain(id).unwrap_or(f64::NEG_INFINITY);
+            gain(b).partial_cmp(&gain(a)).unwrap_or(Ordering::Equal)
+                .then(played(b).cmp(&played(a)))
+                .then(a.cmp(b))
+        });
+        ids
+    }
+
+    pub fn report<'a>(&'a self, deck: &'a Deck) -> ImpactReport<'a> {
+        ImpactReport { stats: self, deck }
+    }
+}
+
+// A ranked table of the cards in one deck
+pub struct ImpactReport<'a> {
+    stats: &'a ImpactStats,
+    deck: &'a Deck,
+}
+
+fn percent(rate: Option<f64>) -> String {
+    rate.map_or("-".to_string(), |rate| format!("{:.1}", 100.0 * rate))
+}
+
+impl<'a> fmt::Display for ImpactReport<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<26} {:<8} {:>6} {:>6} {:>9} {:>6} {:>5} {:>8} {:>8} {:>7}",
+                 "Card", "House", "Drawn", "Played", "Discarded", "Amber", "Kills", "Win% in", "Win% out", "Gain")?;
+        let none = CardImpact::default();
+        for id in self.stats.ranked(self.deck) {
+            let details = &self.deck.card_details[&id];
+            let impact = self.stats.cards.get(&id).unwrap_or(&none);
+            let gain = self.stats.win_rate_gain(&id).map_or("-".to_string(), |gain| format!("{:+.1}", 100.0 * gain));
+            writeln!(f, "{:<26} {:<8} {:>6} {:>6} {:>9} {:>6} {:>5} {:>8} {:>8} {:>7}",
+                     details.card_title, details.house.to_string(), impact.drawn, impact.played,
+                     impact.discarded, impact.amber, impact.kills, percent(self.stats.win_rate_played(&id)),
+                     percent(self.stats.win_rate_not_played(&id)), gain)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ImpactStats;
+    use api::test::deck_from;
+    use game::test::test_card;
+    use game::{Flank, House, Type};
+    use replay::LogEvent;
+
+    fn card(id: &str) -> String {
+        id.to_string()
+    }
+
+    #[test]
+    fn test_impact_stats() {
+        let game = vec![
+            LogEvent::Draw { seat: 0, card: card("a") },
+            LogEvent::Draw { seat: 0, card: card("b") },
+            LogEvent::Draw { seat: 1, card: card("x") },
+            LogEvent::Play { seat: 0, card: card("a"), slot: 0, flank: Flank::Right },
+            LogEvent::Amber { seat: 0, card: card("a"), amount: 1 },
+            LogEvent::Play { seat: 1, card: card("x"), slot: 0, flank: Flank::Right },
+            LogEvent::Destroyed { seat: 1, creature: 0, card: card("x"), by: Some(card("a")) },
+            LogEvent::Discard { seat: 1, card: card("x") },
+            LogEvent::Reap { seat: 0, creature: 0 },
+            LogEvent::Amber { seat: 0, card: card("a"), amount: 1 },
+        ];
+        let mut stats = ImpactStats::default();
+        stats.record(&game, 0, true);
+        stats.record(&game[..3], 0, false);
+        stats.record(&game[..3], 0, false);
+        let a = &stats.cards["a"];
+        assert_eq!((a.drawn, a.played, a.amber, a.kills, a.discarded), (3, 1, 2, 1, 0));
+        assert_eq!(stats.win_rate_played("a"), Some(1.0));
+        assert_eq!(stats.win_rate_not_played("a"), Some(0.0));
+        assert_eq!(stats.win_rate_gain("a"), Some(1.0));
+        assert_eq!(stats.win_rate_played("b"), None);
+        assert!(!stats.cards.contains_key("x"));
+
+        let mut other = ImpactStats::default();
+        other.record(&game, 1, false);
+        assert_eq!(other.cards["x"].discarded, 1);
+        other.merge(&stats);
+        assert_eq!(other.games, 4);
+        assert_eq!(other.cards["a"].drawn, 3);
+    }
+
+    #[test]
+    fn test_impact_mulligan() {
+        let game = vec![
+            LogEvent::Draw { seat: 0, card: card("a") },
+            LogEvent::Draw { seat: 0, card: card("b") },
+            LogEvent::Draw { seat: 1, card: card("x") },
+            // The hand sent back doesn't count as drawn, the new one does
+            LogEvent::Mulligan { seat: 0 },
+            LogEvent::Draw { seat: 0, card: card("a") },
+            LogEvent::ChooseHouse { seat: 0, house: House::Brobnar },
+            LogEvent::EndTurn { seat: 0 },
+            LogEvent::Draw { seat: 0, card: card("b") },
+        ];
+        let mut stats = ImpactStats::default();
+        stats.record(&game, 0, true);
+        assert_eq!((stats.cards["a"].drawn, stats.cards["b"].drawn), (1, 1));
+
+        // Nothing is taken back for the other seat's mulligan, or once the game is under way
+        let mut other = ImpactStats::default();
+        other.record(&game[..3], 1, false);
+        other.record(&[game[5].clone(), game[7].clone(), game[3].clone()], 0, false);
+        assert_eq!(other.cards["x"].drawn, 1);
+        assert_eq!(other.cards["b"].drawn, 1);
+    }
+
+    #[test]
+    fn test_impact_ranking() {
+        let mut deck = deck_from(test_card(House::Brobnar, Type::Creature, 0, 3, 0), 18);
+        let other = test_card(House::Brobnar, Type::Action, 1, 0, 0);
+        let (creature, action) = (deck.cards[0].clone(), other.id.clone());
+        deck.card_details.insert(action.clone(), other);
+
+        let play = |id: &String| LogEvent::Play { seat: 0, card: id.clone(), slot: 0, flank: Flank::Right };
+        let mut stats = ImpactStats::default();
+        stats.record(&[play(&action)], 0, true);
+        stats.record(&[play(&action), play(&creature)], 0, false);
+        stats.record(&[], 0, false);
+        assert_eq!(stats.ranked(&deck), vec![action.clone(), creature]);
+
+        let report = stats.report(&deck).to_string();
+        assert_eq!(report.lines().count(), 3);
+        assert!(report.lines().nth(1).unwrap().ends_with("50.0      0.0   +50.0"));
+    }
+}
@@ -1,14 +1,15 @@
-#![feature(vec_remove_item)]
 
 #[macro_use]
 extern crate log;
 #[macro_use]
 extern crate serde_derive;
 
+extern crate env_logger;
 extern crate serde;
 extern crate serde_json;
 extern crate reqwest;
 extern crate rand;
+extern crate rlua;
 extern crate uuid;
 
 mod game;
@@ -17,26 +18,364 @@ mod board;
 mod field;
 mod turn;
 mod player;
+mod trigger;
+mod simulation;
+mod runner;
+mod tournament;
+mod view;
+mod replay;
+mod snapshot;
+mod impact;
+mod houses;
+mod timeline;
 
+use player::PlayerKind;
+use player::human::render_board;
+use rand::Rng;
+use simulation::Series;
+use std::env;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::process;
+use std::sync::Arc;
+use view::BoardView;
 
 static DECK_ID: &str = "8339ebd0-bd62-47b5-9274-37394546b238";
 
-struct Game {
-    first_player: Box<dyn player::Player>,
+const USAGE: &str = "Usage:
+  keyforge-sim                     print an opening hand
+  keyforge-sim simulate <deck A> <deck B> [options]
+  keyforge-sim tournament [deck folder] [options]
+  keyforge-sim replay <replay file> [--step <n>] [--save <snapshot file>]
+  keyforge-sim resume <snapshot file> [options]
 
+Decks are deck ids or paths to saved deck files. A tournament plays every
+pair of decks saved in the folder (default decks) and rates them. A replay
+checks that a saved game still plays out the same, or with --step shows the
+game after its first n events. --save writes a snapshot of the game at that
+point, which resume plays on from; snapshots can also be edited into puzzles.
+
+Simulate options:
+  --player-a <player>   who plays deck A (default greedy)
+  --player-b <player>   who plays deck B (default greedy)
+  --games <n>           games to play, each side going first in half (default 100)
+  --replays <folder>    save a replay of every game
+  --timeline <file>     write amber, key and board curves by turn as CSV
+  --cards <a|b>         rank the cards of deck A or B by how much they win games
+  --houses <a|b>        show the houses deck A or B picks and its opening hands
+
+Resume options:
+  --player-a <player>   who plays seat 0, the first player (default greedy)
+  --player-b <player>   who plays seat 1 (default greedy)
+
+Tournament options:
+  --player <player>     who plays every deck (default greedy)
+  --games <n>           games per pair in each seat order (default 50)
+  --json <file>         also write the results as JSON
+
+Common options:
+  --seed <n>            seed for reproducible runs (default random)
+  --threads <n>         games to play at once (default 1)
+
+Players: random, greedy, human, mcts[:<iterations>[:greedy|random]], lua:<script>, external:<command>";
+
+struct SimulateOptions {
+    decks: [String; 2],
+    players: [PlayerKind; 2],
+    games: u32,
+    seed: u64,
+    threads: usize,
+    replays: Option<String>,
+    timeline: Option<String>,
+    // Side to report card impact for
+    cards: Option<usize>,
+    // Side to report house choices and opening hands for
+    houses: Option<usize>,
+}
+
+fn parse_side(option: &str, value: &str) -> Result<usize, String> {
+    match value.to_lowercase().as_str() {
+        "a" => Ok(0),
+        "b" => Ok(1),
+        _ => Err(format!("{} takes a or b, not {}", option, value)),
+    }
+}
+
+fn parse_simulate(args: &[String]) -> Result<SimulateOptions, String> {
+    let mut decks = Vec::new();
+    let mut players = [PlayerKind::Greedy, PlayerKind::Greedy];
+    let mut games = 100;
+    let mut seed = rand::thread_rng().gen();
+    let mut threads = 1;
+    let mut replays = None;
+    let mut timeline = None;
+    let mut cards = None;
+    let mut houses = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            decks.push(arg.clone());
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--player-a" => players[0] = value.parse()?,
+            "--player-b" => players[1] = value.parse()?,
+            "--games" => games = value.parse().map_err(|_| format!("Bad game count: {}", value))?,
+            "--seed" => seed = value.parse().map_err(|_| format!("Bad seed: {}", value))?,
+            "--threads" => threads = value.parse().map_err(|_| format!("Bad thread count: {}", value))?,
+            "--replays" => replays = Some(value.clone()),
+            "--timeline" => timeline = Some(value.clone()),
+            "--cards" => cards = Some(parse_side(arg, value)?),
+            "--houses" => houses = Some(parse_side(arg, value)?),
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    if decks.len() != 2 {
+        return Err("simulate takes exactly two decks".to_string());
+    }
+    Ok(SimulateOptions {
+        decks: [decks[0].clone(), decks[1].clone()],
+        players,
+        games,
+        seed,
+        threads,
+        replays,
+        timeline,
+        cards,
+        houses,
+    })
+}
+
+struct TournamentOptions {
+    dir: String,
+    player: PlayerKind,
+    games: u32,
+    seed: u64,
+    threads: usize,
+    json: Option<String>,
+}
+
+fn parse_tournament(args: &[String]) -> Result<TournamentOptions, String> {
+    let mut dirs = Vec::new();
+    let mut player = PlayerKind::Greedy;
+    let mut games = 50;
+    let mut seed = rand::thread_rng().gen();
+    let mut threads = 1;
+    let mut json = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            dirs.push(arg.clone());
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--player" => player = value.parse()?,
+            "--games" => games = value.parse().map_err(|_| format!("Bad game count: {}", value))?,
+            "--seed" => seed = value.parse().map_err(|_| format!("Bad seed: {}", value))?,
+            "--threads" => threads = value.parse().map_err(|_| format!("Bad thread count: {}", value))?,
+            "--json" => json = Some(value.clone()),
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    if dirs.len() > 1 {
+        return Err("tournament takes one deck folder".to_string());
+    }
+    Ok(TournamentOptions {
+        dir: dirs.pop().unwrap_or_else(|| "decks".to_string()),
+        player,
+        games,
+        seed,
+        threads,
+        json,
+    })
+}
+
+fn run_tournament(args: &[String]) -> Result<(), String> {
+    let options = parse_tournament(args)?;
+    let (names, decks): (Vec<String>, Vec<Arc<api::Deck>>) = tournament::load_decks(&options.dir)?
+        .into_iter()
+        .map(|(name, deck)| (name, Arc::new(deck)))
+        .unzip();
+    if decks.len() < 2 {
+        return Err(format!("{} needs at least two decks for a tournament", options.dir));
+    }
+    println!("{} decks, {:?}, seed {}", decks.len(), options.player, options.seed);
+    let results = tournament::play_round_robin(&decks, &options.player, options.games,
+                                               options.seed, options.threads)?;
+    let report = tournament::Report::new(names, &options.player, options.games, options.seed, results);
+    print!("{}", report);
+    if let Some(path) = options.json {
+        let file = File::create(&path).map_err(|err| format!("Couldn't create {}: {}", path, err))?;
+        serde_json::to_writer_pretty(file, &report).map_err(|err| format!("Couldn't write {}: {}", path, err))?;
+    }
+    Ok(())
+}
+
+fn house_names(houses: &[game::House]) -> String {
+    houses.iter().map(game::House::name).collect::<Vec<_>>().join(", ")
+}
+
+// e.g. "Common 20, Rare 4"
+fn counts<K, F: Fn(&K) -> String>(counts: &[(K, usize)], name: F) -> String {
+    counts.iter().map(|&(ref key, count)| format!("{} {}", name(key), count)).collect::<Vec<_>>().join(", ")
+}
+
+fn simulate(args: &[String]) -> Result<(), String> {
+    let options = parse_simulate(args)?;
+    let load = |name: &String| api::find_deck(name).map_err(|err| format!("{}: {}", name, err));
+    let deck_a = load(&options.decks[0])?;
+    let deck_b = load(&options.decks[1])?;
+    println!("A: {} ({:?}) {}", options.decks[0], options.players[0], house_names(&deck_a.houses));
+    println!("B: {} ({:?}) {}", options.decks[1], options.players[1], house_names(&deck_b.houses));
+    println!("Seed: {}", options.seed);
+    let mut series = Series::new([Arc::new(deck_a), Arc::new(deck_b)], options.players,
+                                 options.games, options.seed);
+    series.names = options.decks;
+    if let Some(dir) = options.replays {
+        fs::create_dir_all(&dir).map_err(|err| format!("Couldn't create {}: {}", dir, err))?;
+        series.replays = Some(dir);
+    }
+    let stats = runner::run_series(&series, options.threads)?;
+    println!("{}", stats);
+    if let Some(side) = options.cards {
+        println!("\nCards of deck {}:", ["A", "B"][side]);
+        print!("{}", stats.cards[side].report(&series.decks[side]));
+    }
+    if let Some(side) = options.houses {
+        println!("\nHouses of deck {}:", ["A", "B"][side]);
+        print!("{}", stats.houses[side]);
+    }
+    if let Some(path) = options.timeline {
+        let file = File::create(&path).map_err(|err| format!("Couldn't create {}: {}", path, err))?;
+        stats.timeline.write_csv(file).map_err(|err| format!("Couldn't write {}: {}", path, err))?;
+    }
+    Ok(())
+}
+
+fn load_decks(names: &[String; 2]) -> Result<[api::Deck; 2], String> {
+    let load = |name: &String| api::find_deck(name).map_err(|err| format!("{}: {}", name, err));
+    Ok([load(&names[0])?, load(&names[1])?])
+}
+
+fn resume(args: &[String]) -> Result<(), String> {
+    let mut path = None;
+    let mut players = [PlayerKind::Greedy, PlayerKind::Greedy];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") && path.is_none() {
+            path = Some(arg);
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--player-a" => players[0] = value.parse()?,
+            "--player-b" => players[1] = value.parse()?,
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    let path = path.ok_or("resume needs a snapshot file")?;
+    let file = File::open(path).map_err(|err| format!("Couldn't open {}: {}", path, err))?;
+    let snapshot = snapshot::GameSnapshot::read(BufReader::new(file))?;
+    let decks = load_decks(&snapshot.names())?;
+    let mut rng = rand::thread_rng();
+    let mut game = simulation::Game::resume(&snapshot, [&decks[0], &decks[1]],
+                                            players[0].create(rng.gen())?, players[1].create(rng.gen())?)?;
+    println!("Resuming at turn {}, seat {} to play", snapshot.turns + 1, snapshot.active);
+    let result = game.play();
+    match result.winner {
+        Some(seat) => println!("Seat {} ({:?}) won after {} turns", seat, players[seat], result.turns),
+        None => println!("Draw after {} turns", result.turns),
+    }
+    Ok(())
+}
+
+fn show_replay(args: &[String]) -> Result<(), String> {
+    let mut path = None;
+    let mut step = None;
+    let mut save = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--step" => {
+                let value = args.next().ok_or("--step needs a value")?;
+                step = Some(value.parse().map_err(|_| format!("Bad step: {}", value))?);
+            }
+            "--save" => save = Some(args.next().ok_or("--save needs a value")?),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg)),
+        }
+    }
+    let path = path.ok_or("replay needs a replay file")?;
+    let file = File::open(path).map_err(|err| format!("Couldn't open {}: {}", path, err))?;
+    let events = replay::read_log(BufReader::new(file))?;
+    let names = replay::deck_names(&events)?;
+    let decks = load_decks(&names)?;
+    let count = events.len();
+    let mut replayer = replay::Replayer::new([&decks[0], &decks[1]], events)?;
+
+    match step {
+        Some(step) => {
+            replayer.run_to(step)?;
+            if let Some(save) = save {
+                let file = File::create(save).map_err(|err| format!("Couldn't create {}: {}", save, err))?;
+                replayer.snapshot().write(file).map_err(|err| format!("Couldn't write {}: {}", save, err))?;
+            }
+            println!("After {} of {} events{}", replayer.position(), count,
+                     replayer.house().map_or(String::new(), |house| format!(", playing {}", house)));
+            for (seat, name) in names.iter().enumerate() {
+                let board = replayer.board(seat);
+                let title = format!("Seat {}: {}", seat, name);
+                print!("{}", render_board(&title, &BoardView::new(board), Some(&board.hand)));
+            }
+        }
+        None if save.is_some() => return Err("--save needs a --step".to_string()),
+        None => {
+            replayer.verify()?;
+            println!("{} events, the replay matches", count);
+        }
+    }
+    Ok(())
 }
 
 fn main() {
+    env_logger::init();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command: fn(&[String]) -> Result<(), String> = match args.first().map(String::as_str) {
+        Some("simulate") => simulate,
+        Some("tournament") => run_tournament,
+        Some("replay") => show_replay,
+        Some("resume") => resume,
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            process::exit(1);
+        }
+        None => show_opening_hand,
+    };
+    if let Err(err) = command(args.get(1..).unwrap_or(&[])) {
+        eprintln!("{}\n\n{}", err, USAGE);
+        process::exit(1);
+    }
+}
+
+fn show_opening_hand(_args: &[String]) -> Result<(), String> {
     match api::get_deck(DECK_ID) {
         Ok(deck_details) => {
-            println!("Houses: {:?}", deck_details.houses);
-            let mut deck = board::DeckBoard::new(&deck_details);
+            println!("Houses: {}", house_names(&deck_details.houses));
+            let sets = deck_details.count_by(|card| card.expansion);
+            println!("Sets: {}", counts(&sets, |set| set.map_or("unknown".to_string(), |set| set.to_string())));
+            let rarities = deck_details.count_by(|card| card.rarity.clone());
+            println!("Rarities: {}", counts(&rarities, |rarity| rarity.as_ref()
+                .map_or("unknown".to_string(), |rarity| rarity.to_string())));
+            let mavericks = deck_details.cards.iter().filter(|id| deck_details.card_details[*id].is_maverick).count();
+            println!("Mavericks: {}", mavericks);
+            let deck = board::DeckBoard::new(&deck_details);
             for card in deck.hand {
-                println!("{house:?} | {card_title} | {card_type:?} ", card_title = card.card_title, house = card.house, card_type = card.card_type);
+                println!("{house} | {card_title} | {card_type} ", card_title = card.card_title, house = card.house, card_type = card.card_type);
             }
         }
-        Err(err) => {
-            panic!("Didn't get deck: {}", err)
-        }
+        Err(err) => return Err(format!("Didn't get deck: {}", err)),
     }
+    Ok(())
 }
@@ -0,0 +1,404 @@
+// A player run as a separate process, so bots can be written in any language.
+//
+// The simulator writes one JSON request per line to the bot's stdin and
+// expects one JSON response per line on its stdout.
+//
+// Requests:
+//
+//   {"id": 7, "type": "choose_house", "houses": ["Brobnar", "Dis", "Logos"],
+//    "state": {"mine": <board>, "opponent": <board>}}
+//
+//   {"id": 8, "type": "next_action", "houses": [...], "state": {...},
+//    "house": "Brobnar", "actions": [<action>, ...]}
+//
+//   <board>  = {"amber", "keys", "chains", "hand_size", "deck_size", "archives_size",
+//               "discard": [<card>], "purged": [<card>],
+//               "creatures": [<creature>], "artifacts": [<card>]}
+//              and "hand": [<card>] on our own board only
+//   <card>   = a card as the Master Vault API describes it, e.g.
+//              {"id", "card_title", "house", "card_type", "amber", "power", "armor", ...}
+//              plus "enhancements": ["capture", ...] if this copy was enhanced
+//   <creature> = {"card": <card>, "power", "damage", "armor", "captured",
+//                 "exhausted", "stunned", "warded", "elusive", "skirmish", "on_flank"}
+//   <action> = {"type": "play", "card": <card>, "flank": "Left" | "Right"}
+//            | {"type": "reap", "creature": 0}
+//            | {"type": "fight", "creature": 0, "target": 1}
+//
+// Creature indices are 0-based positions in the board's "creatures" list.
+//
+// Responses echo the request id:
+//
+//   {"id": 7, "house": "Brobnar"}
+//   {"id": 8, "action": 2}       index into "actions"
+//   {"id": 8, "action": null}    end the turn
+//
+// A bot that doesn't answer within the timeout, or answers with something
+// invalid, picks its first house and ends its turn. Late answers to earlier
+// requests are ignored, and an answer to a request that hasn't been sent yet
+// counts as invalid.
+
+use field::Creature;
+use game::{BonusIcon, Card, CardDetails, Flank, House};
+use player::Player;
+use serde_json;
+use std::cell::{Cell, RefCell};
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use turn::Action;
+use view::{BoardView, PlayerView};
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Bots are sent the cards themselves, not which copy they are, along with
+// any icons the copy was enhanced with
+#[derive(Serialize)]
+struct CardMessage<'a> {
+    #[serde(flatten)]
+    details: &'a CardDetails,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    enhancements: &'a [BonusIcon],
+}
+
+impl<'a> CardMessage<'a> {
+    fn new(card: &Card<'a>) -> Self {
+        CardMessage {
+            details: card.details,
+            enhancements: card.enhancements,
+        }
+    }
+}
+
+fn cards<'a>(cards: &[Card<'a>]) -> Vec<CardMessage<'a>> {
+    cards.iter().map(CardMessage::new).collect()
+}
+
+#[derive(Serialize)]
+struct CreatureMessage<'a> {
+    card: CardMessage<'a>,
+    power: u32,
+    damage: u32,
+    armor: u32,
+    captured: u32,
+    exhausted: bool,
+    stunned: bool,
+    warded: bool,
+    elusive: bool,
+    skirmish: bool,
+    on_flank: bool,
+}
+
+impl<'a> CreatureMessage<'a> {
+    fn new(creature: &Creature<'a>) -> Self {
+        CreatureMessage {
+            card: CardMessage::new(&creature.details),
+            power: creature.power(),
+            damage: creature.damage,
+            armor: creature.armor,
+            captured: creature.captured,
+            exhausted: creature.exhausted,
+            stunned: creature.stunned,
+            warded: creature.warded,
+            elusive: creature.elusive,
+            skirmish: creature.skirmish,
+            on_flank: creature.on_flank,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BoardMessage<'a> {
+    amber: u32,
+    keys: u32,
+    chains: u32,
+    hand_size: usize,
+    deck_size: usize,
+    archives_size: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hand: Option<Vec<CardMessage<'a>>>,
+    discard: Vec<CardMessage<'a>>,
+    purged: Vec<CardMessage<'a>>,
+    creatures: Vec<CreatureMessage<'a>>,
+    artifacts: Vec<CardMessage<'a>>,
+}
+
+impl<'a, 'b> BoardMessage<'a> {
+    fn new(board: &BoardView<'a, 'b>, hand: Option<&'b [Card<'a>]>) -> Self {
+        BoardMessage {
+            amber: board.amber,
+            keys: board.keys,
+            chains: board.chains,
+            hand_size: board.hand_size,
+            deck_size: board.deck_size,
+            archives_size: board.archives_size,
+            hand: hand.map(cards),
+            discard: cards(board.discard),
+            purged: cards(board.purged),
+            creatures: board.creatures.iter().map(CreatureMessage::new).collect(),
+            artifacts: board.artifacts.iter().map(|a| CardMessage::new(&a.details)).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StateMessage<'a> {
+    mine: BoardMessage<'a>,
+    opponent: BoardMessage<'a>,
+}
+
+impl<'a, 'b> StateMessage<'a> {
+    fn new(view: &PlayerView<'a, 'b>) -> Self {
+        StateMessage {
+            mine: BoardMessage::new(&view.mine, Some(view.hand)),
+            opponent: BoardMessage::new(&view.opponent, None),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ActionMessage<'a> {
+    Play { card: CardMessage<'a>, flank: Flank },
+    Reap { creature: usize },
+    Fight { creature: usize, target: usize },
+}
+
+impl<'a> ActionMessage<'a> {
+    fn new(action: &Action<'a>) -> Self {
+        match *action {
+            Action::Play(card, flank) => ActionMessage::Play { card: CardMessage::new(&card), flank },
+            Action::Reap(creature) => ActionMessage::Reap { creature },
+            Action::Fight(creature, target) => ActionMessage::Fight { creature, target },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Request<'a, 'b> {
+    id: u64,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    houses: &'b [House],
+    state: StateMessage<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    house: Option<House>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actions: Option<Vec<ActionMessage<'a>>>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    id: u64,
+    house: Option<House>,
+    action: Option<usize>,
+}
+
+pub struct ExternalPlayer {
+    child: RefCell<Child>,
+    stdin: RefCell<ChildStdin>,
+    responses: Receiver<String>,
+    next_id: Cell<u64>,
+    pub timeout: Duration,
+}
+
+impl ExternalPlayer {
+    pub fn spawn(program: &str, args: &[&str]) -> io::Result<ExternalPlayer> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        // Read on a separate thread so a silent bot can be timed out
+        let (sender, responses) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => if sender.send(line).is_err() {
+                        break;
+                    },
+                    Err(_) => break,
+                }
+            }
+        });
+
+        Ok(ExternalPlayer {
+            child: RefCell::new(child),
+            stdin: RefCell::new(stdin),
+            responses,
+            next_id: Cell::new(0),
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    // Sends a request and waits for the response to it
+    fn exchange(&self, mut request: Request) -> Option<Response> {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        request.id = id;
+
+        let sent = serde_json::to_string(&request)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            .and_then(|line| {
+                let mut stdin = self.stdin.borrow_mut();
+                writeln!(stdin, "{}", line).and_then(|_| stdin.flush())
+            });
+        if let Err(err) = sent {
+            error!("Couldn't send request to external player: {}", err);
+            return None;
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.responses.recv_timeout(remaining) {
+                Ok(line) => match serde_json::from_str::<Response>(&line) {
+                    Ok(response) if response.id == id => return Some(response),
+                    Ok(ref response) if response.id < id => continue,
+                    Ok(response) => {
+                        warn!("External player answered request {} while request {} was waiting",
+                              response.id, id);
+                        return None;
+                    }
+                    Err(err) => {
+                        warn!("External player sent invalid response {:?}: {}", line, err);
+                        return None;
+                    }
+                },
+                Err(RecvTimeoutError::Timeout) => {
+                    warn!("External player didn't answer request {} in time", id);
+                    return None;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    error!("External player has exited");
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ExternalPlayer {
+    fn drop(&mut self) {
+        let mut child = self.child.borrow_mut();
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+impl Player for ExternalPlayer {
+    fn choose_house(&self, view: &PlayerView) -> House {
+        let houses = view.mine.houses;
+        let request = Request {
+            id: 0,
+            kind: "choose_house",
+            houses,
+            state: StateMessage::new(view),
+            house: None,
+            actions: None,
+        };
+        match self.exchange(request).and_then(|r| r.house) {
+            Some(house) if houses.contains(&house) => house,
+            _ => houses[0].clone(),
+        }
+    }
+
+    fn next_action<'a, 'b>(&self, view: &PlayerView<'a, 'b>) -> Option<Action<'a>> {
+        let actions = &view.actions;
+        let request = Request {
+            id: 0,
+            kind: "next_action",
+            houses: view.mine.houses,
+            state: StateMessage::new(view),
+            house: view.house.clone(),
+            actions: Some(actions.iter().map(ActionMessage::new).collect()),
+        };
+        let index = self.exchange(request)?.action?;
+        if index >= actions.len() {
+            warn!("External player picked action {} of {}, ending turn", index, actions.len());
+        }
+        actions.get(index).cloned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ExternalPlayer;
+    use api::test::with_turn;
+    use game::test::test_card;
+    use game::{Flank, House, Type};
+    use player::Player;
+    use std::time::Duration;
+    use turn::Action;
+
+    #[test]
+    fn test_echo_bot() {
+        with_turn(|turn| {
+            let player = ExternalPlayer::spawn("sh", &["bots/echo_bot.sh"]).unwrap();
+            assert_eq!(player.choose_house(&turn.view()), House::Brobnar);
+            let card = turn.mine().hand[0];
+            assert_eq!(player.next_action(&turn.view()), Some(Action::Play(card, Flank::Right)));
+            assert_eq!(player.next_action(&turn.view()), Some(Action::Play(card, Flank::Right)));
+        });
+    }
+
+    #[test]
+    fn test_silent_bot_times_out() {
+        with_turn(|turn| {
+            let mut player = ExternalPlayer::spawn("sh", &["-c", "cat > /dev/null"]).unwrap();
+            player.timeout = Duration::from_millis(50);
+            assert_eq!(player.choose_house(&turn.view()), House::Brobnar);
+            assert_eq!(player.next_action(&turn.view()), None);
+        });
+    }
+
+    #[test]
+    fn test_late_answers_are_ignored() {
+        with_turn(|turn| {
+            // Answers every request twice, the second time too late to count
+            let script = "while IFS= read -r line; do \
+                              id=$(printf '%s\\n' \"$line\" | sed -n 's/^{\"id\":\\([0-9]*\\).*/\\1/p'); \
+                              printf '{\"id\":%s,\"action\":1}\\n' \"$id\"; \
+                              printf '{\"id\":%s,\"action\":0}\\n' \"$id\"; \
+                          done";
+            let player = ExternalPlayer::spawn("sh", &["-c", script]).unwrap();
+            assert_eq!(player.next_action(&turn.view()), Some(Action::Play(turn.mine().hand[0], Flank::Left)));
+            assert_eq!(player.next_action(&turn.view()), Some(Action::Play(turn.mine().hand[0], Flank::Left)));
+        });
+    }
+
+    #[test]
+    fn test_answers_ahead_are_rejected() {
+        with_turn(|turn| {
+            // Answers as if it was always a request ahead
+            let script = "while IFS= read -r line; do \
+                              id=$(printf '%s\\n' \"$line\" | sed -n 's/^{\"id\":\\([0-9]*\\).*/\\1/p'); \
+                              printf '{\"id\":%s,\"action\":0}\\n' $((id + 1)); \
+                          done";
+            let mut player = ExternalPlayer::spawn("sh", &["-c", script]).unwrap();
+            player.timeout = Duration::from_secs(1);
+            assert_eq!(player.next_action(&turn.view()), None);
+        });
+    }
+
+    #[test]
+    fn test_enhanced_card_message() {
+        use game::{BonusIcon, Card};
+        use serde_json::{self, Value};
+        use super::CardMessage;
+
+        let details = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        let mut card = Card::new(3, &details);
+        let plain = serde_json::to_value(CardMessage::new(&card)).unwrap();
+        assert_eq!(plain["id"], Value::from(details.id.as_str()));
+        assert!(plain.get("enhancements").is_none());
+        card.enhancements = &[BonusIcon::Capture];
+        let enhanced = serde_json::to_value(CardMessage::new(&card)).unwrap();
+        assert_eq!(enhanced["id"], plain["id"]);
+        assert_eq!(enhanced["enhancements"], serde_json::json!(["capture"]));
+    }
+}
@@ -0,0 +1,167 @@
+use game::House;
+use player::Player;
+use turn::Action;
+use view::PlayerView;
+
+// How much a single amber is worth compared to a point of creature power
+const AMBER_VALUE: i32 = 3;
+// Opening hands without this many cards of one house are sent back
+const MULLIGAN_BELOW: usize = 3;
+
+// Deterministic baseline AI that takes whichever action looks best right now
+pub struct GreedyPlayer;
+
+impl GreedyPlayer {
+    // Amber gained, enemy power removed and own power lost by taking the action
+    pub fn score<'a, 'b>(view: &PlayerView<'a, 'b>, action: &Action<'a>) -> i32 {
+        match *action {
+            Action::Play(card, _) => card.amber as i32 * AMBER_VALUE,
+            Action::Reap(_) => AMBER_VALUE,
+            Action::Fight(my_index, target_index) => {
+                let mut attacker = view.mine.creatures[my_index].clone();
+                let mut defender = view.opponent.creatures[target_index].clone();
+                attacker.fight(&mut defender);
+                let mut score = 0;
+                if !defender.is_alive() {
+                    // Captured amber goes back to us when the defender dies
+                    score += defender.power() as i32 + defender.captured as i32 * AMBER_VALUE;
+                }
+                if !attacker.is_alive() {
+                    score -= attacker.power() as i32 + attacker.captured as i32 * AMBER_VALUE;
+                }
+                score
+            }
+        }
+    }
+
+    // Immediate amber available from a house, plus how many of its creatures
+    // are in hand or on the board
+    pub fn house_score(view: &PlayerView, house: &House) -> u32 {
+        let mut amber = 0;
+        let mut creatures = 0;
+        for card in view.hand.iter().filter(|c| c.house == *house) {
+            amber += card.amber;
+            if card.card_type.is_creature() {
+                creatures += 1;
+            }
+        }
+        for creature in view.mine.creatures.iter().filter(|c| c.details.house == *house) {
+            if !creature.exhausted {
+                amber += 1;
+            }
+            creatures += 1;
+        }
+        amber + creatures
+    }
+}
+
+impl Player for GreedyPlayer {
+    fn choose_house(&self, view: &PlayerView) -> House {
+        let mut best: Option<(&House, u32)> = None;
+        for house in view.mine.houses {
+            let score = GreedyPlayer::house_score(view, house);
+            match best {
+                Some((_, best_score)) if best_score >= score => {}
+                _ => best = Some((house, score)),
+            }
+        }
+        best.expect("Deck has no houses").0.clone()
+    }
+
+    fn mulligan(&self, view: &PlayerView) -> bool {
+        view.mine.houses.iter()
+            .map(|house| view.hand.iter().filter(|c| c.house == *house).count())
+            .all(|count| count < MULLIGAN_BELOW)
+    }
+
+    fn next_action<'a, 'b>(&self, view: &PlayerView<'a, 'b>) -> Option<Action<'a>> {
+        let mut best: Option<(Action<'a>, i32)> = None;
+        for &action in &view.actions {
+            let score = GreedyPlayer::score(view, &action);
+            match best {
+                Some((_, best_score)) if best_score >= score => {}
+                _ => best = Some((action, score)),
+            }
+        }
+        // Never make a trade that loses more than it gains
+        match best {
+            Some((action, score)) if score >= 0 => Some(action),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GreedyPlayer;
+    use api::Deck;
+    use api::test::deck_from;
+    use board::DeckBoard;
+    use game::test::test_card;
+    use game::{CardDetails, Flank, House, Type};
+    use player::Player;
+    use player::test::TestPlayer;
+    use turn::{Action, Turn};
+    use view::PlayerView;
+
+    fn decks(mine: CardDetails, opponent: CardDetails) -> (Deck, Deck) {
+        (deck_from(mine, 36), deck_from(opponent, 36))
+    }
+
+    #[test]
+    fn test_choose_house() {
+        let (mut deck, other) = decks(
+            test_card(House::Brobnar, Type::Creature, 0, 2, 0),
+            test_card(House::Dis, Type::Creature, 0, 2, 0),
+        );
+        let amber_card = test_card(House::Logos, Type::Action, 1, 0, 0);
+        deck.houses.push(House::Logos);
+        deck.card_details.insert(amber_card.id.clone(), amber_card);
+        let mine = DeckBoard::new(&deck);
+        let opponent = DeckBoard::new(&other);
+        let view = PlayerView::choosing(&mine, &opponent);
+        // Six creatures in hand beat nothing at all
+        assert_eq!(GreedyPlayer.choose_house(&view), House::Brobnar);
+        assert_eq!(GreedyPlayer::house_score(&view, &House::Brobnar), 6);
+        assert_eq!(GreedyPlayer::house_score(&view, &House::Logos), 0);
+    }
+
+    #[test]
+    fn test_prefers_winning_fight() {
+        let (deck, other) = decks(
+            test_card(House::Brobnar, Type::Creature, 0, 3, 0),
+            test_card(House::Dis, Type::Creature, 0, 2, 0),
+        );
+        let mut mine = DeckBoard::new(&deck);
+        let mut opponent = DeckBoard::new(&other);
+        let card = mine.hand[0];
+        mine.play(card, true, Flank::Right);
+        mine.my_turn_over();
+        let card2 = opponent.hand[0];
+        opponent.play(card2, true, Flank::Right);
+        opponent.creatures[0].captured = 1;
+
+        let turn = Turn::new(mine, opponent, Box::new(TestPlayer { house: House::Brobnar }));
+        assert_eq!(GreedyPlayer::score(&turn.view(), &Action::Fight(0, 0)), 2 + 3);
+        assert_eq!(GreedyPlayer.next_action(&turn.view()), Some(Action::Fight(0, 0)));
+    }
+
+    #[test]
+    fn test_avoids_losing_fight() {
+        let (deck, other) = decks(
+            test_card(House::Brobnar, Type::Creature, 0, 2, 0),
+            test_card(House::Dis, Type::Creature, 0, 5, 0),
+        );
+        let mut mine = DeckBoard::new(&deck);
+        let mut opponent = DeckBoard::new(&other);
+        let card = mine.hand[0];
+        mine.play(card, true, Flank::Right);
+        mine.my_turn_over();
+        let card2 = opponent.hand[0];
+        opponent.play(card2, true, Flank::Right);
+
+        let turn = Turn::new(mine, opponent, Box::new(TestPlayer { house: House::Brobnar }));
+        assert_eq!(GreedyPlayer::score(&turn.view(), &Action::Fight(0, 0)), -2);
+        assert_eq!(GreedyPlayer.next_action(&turn.view()), Some(Action::Reap(0)));
+    }
+}
@@ -0,0 +1,380 @@
+use field::Creature;
+use game::{Card, Flank, House};
+use player::Player;
+use std::cell::RefCell;
+use std::fmt::Write as FmtWrite;
+use std::io::{self, BufRead, Write};
+use trigger::Trigger;
+use turn::{Action, Choice, Decision, Target};
+use trigger::Side;
+use view::{BoardView, PlayerView};
+
+const HELP: &str = "Commands:
+  play <hand #> [left|right]   play a card from your hand
+  reap <creature #>            reap with one of your creatures
+  fight <creature #> <enemy #> fight an enemy creature
+  undo                         take back your last action
+  redo                         redo an action you took back
+  end                          end your turn";
+
+// A person at the terminal. Prints the board and reads commands, so games
+// against the AIs can be played and bug reports reproduced by hand.
+pub struct HumanPlayer<R, W> {
+    input: RefCell<R>,
+    output: RefCell<W>,
+}
+
+impl HumanPlayer<io::BufReader<io::Stdin>, io::Stdout> {
+    pub fn stdio() -> Self {
+        HumanPlayer::new(io::BufReader::new(io::stdin()), io::stdout())
+    }
+}
+
+impl<R: BufRead, W: Write> HumanPlayer<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        HumanPlayer {
+            input: RefCell::new(input),
+            output: RefCell::new(output),
+        }
+    }
+
+    fn say(&self, text: &str) {
+        let mut output = self.output.borrow_mut();
+        // Nothing sensible to do if the terminal has gone away
+        let _ = writeln!(output, "{}", text);
+        let _ = output.flush();
+    }
+
+    // Prompts and reads a trimmed line, None once input runs out
+    fn ask(&self, prompt: &str) -> Option<String> {
+        {
+            let mut output = self.output.borrow_mut();
+            let _ = write!(output, "{} ", prompt);
+            let _ = output.flush();
+        }
+        let mut line = String::new();
+        match self.input.borrow_mut().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim().to_string()),
+        }
+    }
+
+    fn show(&self, view: &PlayerView) {
+        self.say(&render_board("Opponent", &view.opponent, None));
+        self.say(&render_board("You", &view.mine, Some(view.hand)));
+    }
+}
+
+fn describe_creature(index: usize, creature: &Creature) -> String {
+    let mut line = format!("  [{}] {} ({}) power {}",
+                           index, creature.details.card_title, creature.details.house, creature.power());
+    if creature.damage > 0 {
+        write!(line, ", {} damage", creature.damage).unwrap();
+    }
+    if creature.armor > 0 {
+        write!(line, ", {} armor", creature.armor).unwrap();
+    }
+    if creature.captured > 0 {
+        write!(line, ", {} captured", creature.captured).unwrap();
+    }
+    for &(flag, name) in &[(creature.on_flank, "flank"), (creature.exhausted, "exhausted"),
+                           (creature.stunned, "stunned"), (creature.warded, "warded")] {
+        if flag {
+            write!(line, ", {}", name).unwrap();
+        }
+    }
+    line
+}
+
+// Renders one side of the table. The hand is only listed when it's ours.
+pub fn render_board(title: &str, board: &BoardView, hand: Option<&[Card]>) -> String {
+    let mut out = String::new();
+    writeln!(out, "== {} == amber {} | keys {} | chains {} | deck {} | discard {} | archives {}",
+             title, board.amber, board.keys, board.chains, board.deck_size,
+             board.discard.len(), board.archives_size).unwrap();
+    writeln!(out, " Creatures:").unwrap();
+    for (index, creature) in board.creatures.iter().enumerate() {
+        writeln!(out, "{}", describe_creature(index, creature)).unwrap();
+    }
+    if !board.artifacts.is_empty() {
+        writeln!(out, " Artifacts:").unwrap();
+        for (index, artifact) in board.artifacts.iter().enumerate() {
+            writeln!(out, "  [{}] {} ({})", index, artifact.details.card_title, artifact.details.house).unwrap();
+        }
+    }
+    match hand {
+        Some(hand) => {
+            writeln!(out, " Hand:").unwrap();
+            for (index, card) in hand.iter().enumerate() {
+                writeln!(out, "  [{}] {} ({} {}) +{} amber",
+                         index, card.card_title, card.house, card.card_type, card.amber).unwrap();
+            }
+        }
+        None => writeln!(out, " Hand: {} cards", board.hand_size).unwrap(),
+    }
+    out
+}
+
+fn parse_index(word: Option<&str>, what: &str) -> Result<usize, String> {
+    word.ok_or_else(|| format!("Missing {}", what))?
+        .parse()
+        .map_err(|_| format!("{} should be a number", what))
+}
+
+// Turns a command into a decision. Only legal actions are accepted.
+pub fn parse_command<'a, 'b>(line: &str, view: &PlayerView<'a, 'b>) -> Result<Decision<'a>, String> {
+    let mut words = line.split_whitespace();
+    let action = match words.next() {
+        Some("end") => return Ok(Decision::EndTurn),
+        Some("undo") if view.undos > 0 => return Ok(Decision::Undo),
+        Some("undo") => return Err("Nothing to undo".to_string()),
+        Some("redo") if view.redos > 0 => return Ok(Decision::Redo),
+        Some("redo") => return Err("Nothing to redo".to_string()),
+        Some("play") => {
+            let index = parse_index(words.next(), "hand #")?;
+            let card = *view.hand.get(index).ok_or("No card there")?;
+            let flank = match words.next() {
+                Some("left") => Flank::Left,
+                Some("right") | None => Flank::Right,
+                Some(other) => return Err(format!("Unknown flank {}", other)),
+            };
+            // With no creatures out, both flanks are the same place
+            let flank = if view.mine.creatures.is_empty() { Flank::Right } else { flank };
+            Action::Play(card, flank)
+        }
+        Some("reap") => Action::Reap(parse_index(words.next(), "creature #")?),
+        Some("fight") => {
+            let index = parse_index(words.next(), "creature #")?;
+            Action::Fight(index, parse_index(words.next(), "enemy #")?)
+        }
+        _ => return Err(HELP.to_string()),
+    };
+    // Only one copy of each card is listed, but any copy can be played
+    let legal = view.actions.iter().any(|&legal| match (legal, action) {
+        (Action::Play(listed, listed_flank), Action::Play(card, flank)) => {
+            listed.details == card.details && listed_flank == flank
+        }
+        _ => legal == action,
+    });
+    if legal {
+        Ok(Decision::Act(action))
+    } else {
+        Err(format!("Can't do that right now ({} house)", view.house.as_ref().unwrap()))
+    }
+}
+
+impl<R: BufRead, W: Write> Player for HumanPlayer<R, W> {
+    fn choose_house(&self, view: &PlayerView) -> House {
+        self.show(view);
+        let houses = view.mine.houses;
+        let names: Vec<String> = houses.iter().map(House::to_string).collect();
+        loop {
+            let answer = match self.ask(&format!("Choose a house ({}):", names.join(", "))) {
+                Some(answer) => answer.to_lowercase(),
+                None => return houses[0].clone(),
+            };
+            if let Some(index) = names.iter().position(|name| name.to_lowercase() == answer) {
+                return houses[index].clone();
+            }
+            self.say("That's not one of your houses");
+        }
+    }
+
+    fn mulligan(&self, view: &PlayerView) -> bool {
+        self.show(view);
+        loop {
+            match self.ask("Keep this hand? (yes, or no to mulligan)").as_deref() {
+                Some("y") | Some("yes") | None => return false,
+                Some("n") | Some("no") => return true,
+                _ => self.say("Answer yes or no"),
+            }
+        }
+    }
+
+    fn next_action<'a, 'b>(&self, view: &PlayerView<'a, 'b>) -> Option<Action<'a>> {
+        match self.next_decision(view) {
+            Decision::Act(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    fn next_decision<'a, 'b>(&self, view: &PlayerView<'a, 'b>) -> Decision<'a> {
+        self.show(view);
+        loop {
+            let line = match self.ask(&format!("[{}] >", view.house.as_ref().unwrap())) {
+                Some(line) => line,
+                None => return Decision::EndTurn,
+            };
+            match parse_command(&line, view) {
+                Ok(decision) => return decision,
+                Err(message) => self.say(&message),
+            }
+        }
+    }
+
+    fn can_undo(&self) -> bool {
+        true
+    }
+
+    fn order_triggers(&self, _view: &PlayerView, triggers: &[Trigger]) -> Vec<usize> {
+        let default: Vec<usize> = (0..triggers.len()).collect();
+        for (index, trigger) in triggers.iter().enumerate() {
+            self.say(&format!("  [{}] {:?} from {:?} {:?}", index, trigger.effect, trigger.side, trigger.source));
+        }
+        loop {
+            let line = match self.ask("Resolve in which order? (blank for as listed)") {
+                Some(ref line) if !line.is_empty() => line.clone(),
+                _ => return default,
+            };
+            let order: Vec<usize> = line.split_whitespace().filter_map(|w| w.parse().ok()).collect();
+            let mut sorted = order.clone();
+            sorted.sort();
+            if sorted == default {
+                return order;
+            }
+            self.say("List every trigger number once");
+        }
+    }
+
+    fn choose_target(&self, view: &PlayerView, choice: Choice, targets: &[Target]) -> Option<usize> {
+        for (index, target) in targets.iter().enumerate() {
+            let name = match *target {
+                Target::Creature(side, creature) => {
+                    let board = if side == Side::Mine { &view.mine } else { &view.opponent };
+                    format!("{:?} {}", side, board.creatures[creature].details.card_title)
+                }
+                Target::Artifact(side, artifact) => {
+                    let board = if side == Side::Mine { &view.mine } else { &view.opponent };
+                    format!("{:?} {}", side, board.artifacts[artifact].details.card_title)
+                }
+                Target::Hand(card) => view.hand[card].details.card_title.clone(),
+            };
+            self.say(&format!("  [{}] {}", index, name));
+        }
+        let prompt = if choice.optional() {
+            format!("{:?} which? (blank to pass)", choice)
+        } else {
+            format!("{:?} which?", choice)
+        };
+        loop {
+            let line = match self.ask(&prompt) {
+                Some(ref line) if !line.is_empty() => line.clone(),
+                // A blank line takes the first target, or passes if allowed
+                _ if choice.optional() => return None,
+                _ => return Some(0),
+            };
+            match line.parse() {
+                Ok(index) if index < targets.len() => return Some(index),
+                _ => self.say("Pick one of the numbers listed"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_command, HumanPlayer};
+    use api::test::{deck_from, with_turn};
+    use board::DeckBoard;
+    use game::test::test_card;
+    use game::{Flank, House, Type};
+    use player::Player;
+    use player::test::TestPlayer;
+    use std::io::Cursor;
+    use trigger::{Effect, Side, Source, Trigger};
+    use turn::{Action, Choice, Decision, Target, Turn};
+    use view::PlayerView;
+
+    #[test]
+    fn test_parse_command() {
+        with_turn(|turn| {
+            let turn = &turn.view();
+            let card = turn.hand[2];
+            assert_eq!(parse_command("play 2 left", turn), Ok(Decision::Act(Action::Play(card, Flank::Left))));
+            assert_eq!(parse_command("play 2", turn), Ok(Decision::Act(Action::Play(card, Flank::Right))));
+            assert_eq!(parse_command("reap 0", turn), Ok(Decision::Act(Action::Reap(0))));
+            assert_eq!(parse_command("fight 0 0", turn), Ok(Decision::Act(Action::Fight(0, 0))));
+            assert_eq!(parse_command("end", turn), Ok(Decision::EndTurn));
+            assert!(parse_command("undo", turn).is_err());
+            assert!(parse_command("fight 0 1", turn).is_err());
+            assert!(parse_command("play 9", turn).is_err());
+            assert!(parse_command("reap x", turn).is_err());
+            assert!(parse_command("dance", turn).is_err());
+        });
+
+        // Either flank of an empty board is the right one
+        let deck = deck_from(test_card(House::Brobnar, Type::Creature, 0, 2, 0), 36);
+        let turn = Turn::resume(DeckBoard::new(&deck), DeckBoard::new(&deck), House::Brobnar,
+                                Box::new(TestPlayer { house: House::Brobnar }));
+        let view = turn.view();
+        let card = view.hand[0];
+        assert_eq!(parse_command("play 0 left", &view), Ok(Decision::Act(Action::Play(card, Flank::Right))));
+    }
+
+    #[test]
+    fn test_human_player() {
+        with_turn(|turn| {
+            let input = Cursor::new("logos\nbrobnar\nreap 3\nreap 0\n");
+            let player = HumanPlayer::new(input, Vec::new());
+            assert_eq!(player.choose_house(&turn.view()), House::Brobnar);
+            assert_eq!(player.next_action(&turn.view()), Some(Action::Reap(0)));
+            // Out of input ends the turn
+            assert_eq!(player.next_action(&turn.view()), None);
+
+            let output = String::from_utf8(player.output.into_inner()).unwrap();
+            assert!(output.contains("== You == amber 0 | keys 0"));
+            assert!(output.contains("[0] test (Brobnar) power 2, flank"));
+            assert!(output.contains("Hand: 5 cards"));
+            assert!(output.contains("That's not one of your houses"));
+            assert!(output.contains("Can't do that right now"));
+        });
+    }
+
+    #[test]
+    fn test_human_undo() {
+        let deck = deck_from(test_card(House::Brobnar, Type::Creature, 0, 2, 0), 36);
+        let mut mine = DeckBoard::new(&deck);
+        let card = mine.hand[0];
+        mine.play(card, true, Flank::Right);
+        mine.my_turn_over();
+        // The second undo has nothing left to take back
+        let input = Cursor::new("reap 0\nundo\nundo\nredo\nend\n");
+        let mut turn = Turn::resume(mine, DeckBoard::new(&deck), House::Brobnar,
+                                    Box::new(HumanPlayer::new(input, Vec::new())));
+        turn.run();
+        assert_eq!(turn.mine().amber, 1);
+        assert!(turn.mine().creatures[0].exhausted);
+        assert!(turn.undo());
+        assert_eq!(turn.mine().amber, 0);
+        assert!(!turn.undo());
+    }
+
+    #[test]
+    fn test_order_triggers() {
+        let deck = deck_from(test_card(House::Brobnar, Type::Creature, 0, 2, 0), 36);
+        let board = DeckBoard::new(&deck);
+        let view = PlayerView::choosing(&board, &board);
+        let player = HumanPlayer::new(Cursor::new("1 1\n2 0 1\n"), Vec::new());
+        let trigger = Trigger {
+            side: Side::Mine,
+            source: Source::Creature(0),
+            effect: Effect::GainAmber(1),
+        };
+        assert_eq!(player.order_triggers(&view, &[trigger, trigger, trigger]), vec![2, 0, 1]);
+        assert_eq!(player.order_triggers(&view, &[trigger, trigger]), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_choose_target() {
+        with_turn(|turn| {
+            let player = HumanPlayer::new(Cursor::new("2\n1\n\n"), Vec::new());
+            let targets = [Target::Creature(Side::Opponent, 0), Target::Creature(Side::Mine, 0)];
+            assert_eq!(player.choose_target(&turn.view(), Choice::Damage, &targets), Some(1));
+            assert_eq!(player.choose_target(&turn.view(), Choice::Discard, &[Target::Hand(0)]), None);
+
+            let output = String::from_utf8(player.output.into_inner()).unwrap();
+            assert!(output.contains("[1] Mine test"));
+            assert!(output.contains("Pick one of the numbers listed"));
+        });
+    }
+}
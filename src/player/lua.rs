@@ -0,0 +1,377 @@
+use field::Creature;
+use game::{Card, CardDetails, Flank, House};
+use player::Player;
+use rlua::{self, Function, Lua, Table, Value};
+use std::fs::File;
+use std::io::Read;
+use turn::Action;
+use view::{BoardView, PlayerView};
+
+// Instructions a script may run per decision before it is cut off
+pub const DEFAULT_INSTRUCTION_LIMIT: u32 = 1_000_000;
+
+// Runs before any script. Keeps hold of debug.sethook for the instruction
+// limit and returns it, along with a function that runs a script in its own
+// environment. The environment only has what a script needs to make
+// decisions, so the debug library and anything else that reaches outside the
+// sandbox is never loaded into it.
+const SANDBOX: &str = r#"
+    local sethook, getinfo, error, load = debug.sethook, debug.getinfo, error, load
+    local limit
+    -- Once the budget runs out every later instruction errors too, so a
+    -- script can't catch the error with pcall and keep going. Only lifting
+    -- the limit is let through.
+    local function exceeded()
+        if getinfo(2, "f").func ~= limit then
+            sethook(exceeded, "", 1)
+            error("instruction limit exceeded")
+        end
+    end
+    limit = function(count)
+        if count then
+            sethook(exceeded, "", count)
+        else
+            sethook()
+        end
+    end
+
+    -- Strings share the string table as their metatable's __index, so these
+    -- go from it as well. rep can ask for any amount of memory in a single
+    -- instruction.
+    string.rep, string.dump = nil
+    local env = {}
+    for _, name in ipairs({"assert", "error", "getmetatable", "ipairs", "next", "pairs", "pcall", "print",
+                           "rawequal", "rawget", "rawlen", "rawset", "select", "setmetatable", "tonumber",
+                           "tostring", "type", "xpcall", "math", "string", "table", "utf8"}) do
+        env[name] = _G[name]
+    end
+    local function run(source)
+        local chunk, err = load(source, "=script", "t", env)
+        if not chunk then
+            error(err, 0)
+        end
+        chunk()
+    end
+    return limit, run, env
+"#;
+
+const LIMIT_KEY: &str = "keyforge_instruction_limit";
+const ENV_KEY: &str = "keyforge_script_env";
+
+// A player whose decisions are made by a Lua script. The script defines
+//
+//   choose_house(state) -> house name, e.g. "Brobnar"
+//   next_action(state)  -> index into state.actions (1-based), or nil to end the turn
+//
+// `state` is a fresh copy of the table on every call, so scripts can't change
+// the game through it. See `board_table` for its layout. A script that errors,
+// runs out of instructions or returns nonsense picks the first house and ends
+// its turn.
+pub struct LuaPlayer {
+    lua: Lua,
+    pub instruction_limit: u32,
+}
+
+impl LuaPlayer {
+    pub fn new(source: &str) -> rlua::Result<LuaPlayer> {
+        // rlua has no instruction hook of its own, so the debug library is
+        // loaded for debug.sethook. Only the sandbox prelude can reach it:
+        // scripts run in an environment that never has it.
+        let player = LuaPlayer {
+            lua: unsafe { Lua::new_with_debug() },
+            instruction_limit: DEFAULT_INSTRUCTION_LIMIT,
+        };
+        {
+            let lua = &player.lua;
+            let (limit, run, env): (Function, Function, Table) = lua.exec(SANDBOX, Some("sandbox"))?;
+            lua.set_named_registry_value(LIMIT_KEY, limit)?;
+            lua.set_named_registry_value(ENV_KEY, env)?;
+            player.limited(|_| run.call::<_, ()>(source))?;
+        }
+        Ok(player)
+    }
+
+    pub fn from_file(path: &str) -> rlua::Result<LuaPlayer> {
+        let mut source = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut source))
+            .map_err(rlua::Error::external)?;
+        LuaPlayer::new(&source)
+    }
+
+    // Runs f with the instruction limit in place
+    fn limited<'lua, R, F>(&'lua self, f: F) -> rlua::Result<R>
+        where F: FnOnce(&'lua Lua) -> rlua::Result<R> {
+        let limit: Function = self.lua.named_registry_value(LIMIT_KEY)?;
+        limit.call::<_, ()>(self.instruction_limit)?;
+        let result = f(&self.lua);
+        limit.call::<_, ()>(())?;
+        result
+    }
+
+    fn call<'lua>(&'lua self, name: &str, state: Table<'lua>) -> rlua::Result<Value<'lua>> {
+        let env: Table = self.lua.named_registry_value(ENV_KEY)?;
+        let function: Function = env.get(name)?;
+        self.limited(|_| function.call(state))
+    }
+
+    fn state<'lua>(&'lua self, view: &PlayerView) -> rlua::Result<Table<'lua>> {
+        let state = self.lua.create_table()?;
+        let houses = view.mine.houses.iter().map(House::name);
+        state.set("houses", self.lua.create_sequence_from(houses)?)?;
+        let mine = board_table(&self.lua, &view.mine)?;
+        mine.set("hand", cards_table(&self.lua, view.hand)?)?;
+        state.set("mine", mine)?;
+        state.set("opponent", board_table(&self.lua, &view.opponent)?)?;
+        if let Some(ref house) = view.house {
+            state.set("house", house.name())?;
+        }
+        Ok(state)
+    }
+
+    fn try_choose_house(&self, view: &PlayerView) -> rlua::Result<Option<House>> {
+        let state = self.state(view)?;
+        let choice = match self.call("choose_house", state)? {
+            Value::String(name) => name.to_str()?.to_string(),
+            _ => return Ok(None),
+        };
+        Ok(view.mine.houses.iter().find(|h| h.name() == choice).cloned())
+    }
+
+    fn try_next_action<'a, 'b>(&self, view: &PlayerView<'a, 'b>) -> rlua::Result<Option<Action<'a>>> {
+        let actions = &view.actions;
+        let state = self.state(view)?;
+        let action_tables = actions.iter()
+            .map(|action| action_table(&self.lua, action))
+            .collect::<rlua::Result<Vec<Table>>>()?;
+        state.set("actions", self.lua.create_sequence_from(action_tables)?)?;
+
+        let index = match self.call("next_action", state)? {
+            Value::Integer(index) => index,
+            Value::Number(index) if index.fract() == 0.0 => index as i64,
+            Value::Nil => return Ok(None),
+            other => {
+                warn!("Lua player returned {:?} as an action, ending turn", other);
+                return Ok(None);
+            }
+        };
+        if index < 1 || index as usize > actions.len() {
+            warn!("Lua player returned action {} of {}, ending turn", index, actions.len());
+            return Ok(None);
+        }
+        Ok(Some(actions[index as usize - 1]))
+    }
+}
+
+fn card_table<'lua>(lua: &'lua Lua, card: &CardDetails) -> rlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.set("id", card.id.as_str())?;
+    table.set("title", card.card_title.as_str())?;
+    table.set("house", card.house.name())?;
+    table.set("type", card.card_type.name())?;
+    table.set("amber", card.amber)?;
+    table.set("power", card.power)?;
+    table.set("armor", card.armor)?;
+    Ok(table)
+}
+
+fn creature_table<'lua>(lua: &'lua Lua, creature: &Creature) -> rlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.set("card", card_table(lua, &creature.details)?)?;
+    table.set("power", creature.power())?;
+    table.set("damage", creature.damage)?;
+    table.set("armor", creature.armor)?;
+    table.set("captured", creature.captured)?;
+    table.set("exhausted", creature.exhausted)?;
+    table.set("stunned", creature.stunned)?;
+    table.set("warded", creature.warded)?;
+    table.set("elusive", creature.elusive)?;
+    table.set("skirmish", creature.skirmish)?;
+    table.set("on_flank", creature.on_flank)?;
+    Ok(table)
+}
+
+fn cards_table<'lua>(lua: &'lua Lua, cards: &[Card]) -> rlua::Result<Table<'lua>> {
+    let tables = cards.iter()
+        .map(|card| card_table(lua, card))
+        .collect::<rlua::Result<Vec<Table>>>()?;
+    lua.create_sequence_from(tables)
+}
+
+// { amber, keys, chains, hand_size, deck_size, archives_size,
+//   discard = {card...}, purged = {card...}, creatures = {creature...},
+//   artifacts = {card...} }
+// where card = { id, title, house, type, amber, power, armor } and
+// creature = { card, power, damage, armor, captured, exhausted, stunned,
+//              warded, elusive, skirmish, on_flank }
+// Our own board also has hand = {card...}.
+fn board_table<'lua>(lua: &'lua Lua, board: &BoardView) -> rlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.set("amber", board.amber)?;
+    table.set("keys", board.keys)?;
+    table.set("chains", board.chains)?;
+    table.set("hand_size", board.hand_size)?;
+    table.set("deck_size", board.deck_size)?;
+    table.set("archives_size", board.archives_size)?;
+    table.set("discard", cards_table(lua, board.discard)?)?;
+    table.set("purged", cards_table(lua, board.purged)?)?;
+    let creatures = board.creatures.iter()
+        .map(|creature| creature_table(lua, creature))
+        .collect::<rlua::Result<Vec<Table>>>()?;
+    table.set("creatures", lua.create_sequence_from(creatures)?)?;
+    let artifacts = board.artifacts.iter()
+        .map(|artifact| card_table(lua, &artifact.details))
+        .collect::<rlua::Result<Vec<Table>>>()?;
+    table.set("artifacts", lua.create_sequence_from(artifacts)?)?;
+    Ok(table)
+}
+
+// { type = "play", card, flank = "left" | "right" }
+// { type = "reap", creature }
+// { type = "fight", creature, target }
+// with creature indices 1-based, like the board tables
+fn action_table<'lua>(lua: &'lua Lua, action: &Action) -> rlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    match *action {
+        Action::Play(card, flank) => {
+            table.set("type", "play")?;
+            table.set("card", card_table(lua, &card)?)?;
+            table.set("flank", match flank {
+                Flank::Left => "left",
+                Flank::Right => "right",
+            })?;
+        }
+        Action::Reap(index) => {
+            table.set("type", "reap")?;
+            table.set("creature", index + 1)?;
+        }
+        Action::Fight(index, target) => {
+            table.set("type", "fight")?;
+            table.set("creature", index + 1)?;
+            table.set("target", target + 1)?;
+        }
+    }
+    Ok(table)
+}
+
+impl Player for LuaPlayer {
+    fn choose_house(&self, view: &PlayerView) -> House {
+        match self.try_choose_house(view) {
+            Ok(Some(house)) => house,
+            Ok(None) => {
+                warn!("Lua player chose a house it doesn't have");
+                view.mine.houses[0].clone()
+            }
+            Err(err) => {
+                error!("Lua player failed to choose a house: {}", err);
+                view.mine.houses[0].clone()
+            }
+        }
+    }
+
+    fn next_action<'a, 'b>(&self, view: &PlayerView<'a, 'b>) -> Option<Action<'a>> {
+        match self.try_next_action(view) {
+            Ok(action) => action,
+            Err(err) => {
+                error!("Lua player failed to pick an action: {}", err);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LuaPlayer;
+    use api::test::with_turn;
+    use game::House;
+    use player::Player;
+    use turn::Action;
+
+    const REAPER: &str = r#"
+        function choose_house(state)
+            return state.houses[1]
+        end
+
+        function next_action(state)
+            for i, action in ipairs(state.actions) do
+                if action.type == "reap" and state.mine.creatures[action.creature].power >= 2 then
+                    return i
+                end
+            end
+            return nil
+        end
+    "#;
+
+    #[test]
+    fn test_lua_player() {
+        with_turn(|turn| {
+            let player = LuaPlayer::new(REAPER).unwrap();
+            assert_eq!(player.choose_house(&turn.view()), House::Brobnar);
+            assert_eq!(player.next_action(&turn.view()), Some(Action::Reap(0)));
+        });
+    }
+
+    #[test]
+    fn test_lua_bad_results() {
+        let script = r#"
+            function choose_house(state) return "Mars" end
+            function next_action(state) return 100 end
+        "#;
+        with_turn(|turn| {
+            let player = LuaPlayer::new(script).unwrap();
+            assert_eq!(player.choose_house(&turn.view()), House::Brobnar);
+            assert_eq!(player.next_action(&turn.view()), None);
+        });
+    }
+
+    #[test]
+    fn test_lua_instruction_limit() {
+        let script = r#"
+            function choose_house(state) while true do end end
+            function next_action(state) return #state.actions end
+        "#;
+        with_turn(|turn| {
+            let player = LuaPlayer::new(script).unwrap();
+            assert_eq!(player.choose_house(&turn.view()), House::Brobnar);
+            // The limit is reset between calls
+            assert!(player.next_action(&turn.view()).is_some());
+        });
+        assert!(LuaPlayer::new("while true do end").is_err());
+    }
+
+    #[test]
+    fn test_lua_instruction_limit_pcall() {
+        let script = r#"
+            function choose_house(state)
+                while true do pcall(function() while true do end end) end
+            end
+            function next_action(state) return #state.actions end
+        "#;
+        with_turn(|turn| {
+            let player = LuaPlayer::new(script).unwrap();
+            assert_eq!(player.choose_house(&turn.view()), House::Brobnar);
+            assert!(player.next_action(&turn.view()).is_some());
+        });
+        let script = "local n = 0 while n < 50 do pcall(function() while true do end end) n = n + 1 end";
+        assert!(LuaPlayer::new(script).is_err());
+    }
+
+    #[test]
+    fn test_lua_sandbox() {
+        let script = r#"
+            function choose_house(state) return os.exit() end
+            function next_action(state)
+                if os == nil and io == nil and debug == nil and load == nil and _G == nil
+                        and string.rep == nil and ("x").rep == nil then
+                    return 1
+                end
+            end
+        "#;
+        with_turn(|turn| {
+            let player = LuaPlayer::new(script).unwrap();
+            assert_eq!(player.choose_house(&turn.view()), House::Brobnar);
+            assert!(player.next_action(&turn.view()).is_some());
+        });
+    }
+}
@@ -0,0 +1,262 @@
+use board::{DeckBoard, KEY_COST, KEYS_TO_WIN};
+use game::House;
+use player::{Player, RandomPlayer};
+use player::greedy::GreedyPlayer;
+use rand::{thread_rng, Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use simulation::Game;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::time::{Duration, Instant};
+use turn::{Action, Turn};
+use view::PlayerView;
+
+// UCT exploration constant
+const EXPLORATION: f64 = 1.41;
+
+// How the rest of the game is played out from a searched position
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum RolloutPolicy {
+    Random,
+    Greedy,
+}
+
+impl RolloutPolicy {
+    fn player(self, rng: &mut StdRng) -> Box<dyn Player> {
+        match self {
+            RolloutPolicy::Random => Box::new(RandomPlayer::seeded(rng.gen())),
+            RolloutPolicy::Greedy => Box::new(GreedyPlayer),
+        }
+    }
+}
+
+struct Node<'a> {
+    // None is ending the turn
+    action: Option<Action<'a>>,
+    visits: u32,
+    reward: f64,
+    children: Vec<Node<'a>>,
+}
+
+impl<'a> Node<'a> {
+    fn new(action: Option<Action<'a>>) -> Self {
+        Node {
+            action,
+            visits: 0,
+            reward: 0.0,
+            children: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, reward: f64) {
+        self.visits += 1;
+        self.reward += reward;
+    }
+
+    fn uct(&self, parent_visits: u32) -> f64 {
+        let visits = f64::from(self.visits);
+        self.reward / visits + EXPLORATION * (f64::from(parent_visits).ln() / visits).sqrt()
+    }
+}
+
+// Monte Carlo tree search over the actions of the current turn. Hidden
+// information is sampled fresh for every iteration, and everything after the
+// turn ends is played out with the rollout policy.
+pub struct MctsPlayer {
+    pub iterations: u32,
+    pub time_limit: Option<Duration>,
+    pub rollout: RolloutPolicy,
+    // Turns a playout runs for before the position is scored instead
+    pub rollout_turns: u32,
+    rng: RefCell<StdRng>,
+}
+
+impl MctsPlayer {
+    pub fn new(iterations: u32, rollout: RolloutPolicy) -> Self {
+        MctsPlayer {
+            iterations,
+            time_limit: None,
+            rollout,
+            rollout_turns: 30,
+            rng: RefCell::new(StdRng::seed_from_u64(thread_rng().gen())),
+        }
+    }
+
+    pub fn seeded(iterations: u32, rollout: RolloutPolicy, seed: u64) -> Self {
+        let player = MctsPlayer::new(iterations, rollout);
+        *player.rng.borrow_mut() = StdRng::seed_from_u64(seed);
+        player
+    }
+
+    fn out_of_time(&self, deadline: Option<Instant>) -> bool {
+        deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    // Walks down the tree applying actions, expands one new node and plays it
+    // out. Returns the reward for the searching player.
+    fn search<'a>(&self, node: &mut Node<'a>, mut turn: Turn<'a>, rng: &mut StdRng) -> f64 {
+        let mut options: Vec<Option<Action<'a>>> = turn.legal_actions().into_iter().map(Some).collect();
+        options.push(None);
+        let untried: Vec<Option<Action<'a>>> = options.iter()
+            .filter(|&option| !node.children.iter().any(|c| c.action == *option))
+            .cloned()
+            .collect();
+
+        let reward = if let Some(&action) = untried.choose(rng) {
+            let mut child = Node::new(action);
+            let reward = match action {
+                Some(action) => {
+                    turn.execute_action(action);
+                    self.rollout(turn, rng)
+                }
+                None => self.finish(turn, rng),
+            };
+            child.record(reward);
+            node.children.push(child);
+            reward
+        } else {
+            // Only children that are legal in this sample can be followed
+            let parent_visits = node.visits;
+            let child = node.children.iter_mut()
+                .filter(|c| options.contains(&c.action))
+                .max_by(|a, b| {
+                    a.uct(parent_visits).partial_cmp(&b.uct(parent_visits)).unwrap_or(Ordering::Equal)
+                })
+                .expect("Ending the turn is always an option");
+            match child.action {
+                Some(action) => {
+                    turn.execute_action(action);
+                    self.search(child, turn, rng)
+                }
+                None => {
+                    let reward = self.finish(turn, rng);
+                    child.record(reward);
+                    reward
+                }
+            }
+        };
+        node.record(reward);
+        reward
+    }
+
+    // Plays out the rest of the turn and the game with the rollout policy
+    fn rollout<'a>(&self, mut turn: Turn<'a>, rng: &mut StdRng) -> f64 {
+        turn.run();
+        self.finish(turn, rng)
+    }
+
+    fn finish<'a>(&self, mut turn: Turn<'a>, rng: &mut StdRng) -> f64 {
+        turn.end();
+        let (mine, opponent, _) = turn.finish();
+        // The opponent goes next, so we are seat 1
+        let mut game = Game::new(opponent, mine, self.rollout.player(rng), self.rollout.player(rng));
+        game.max_turns = self.rollout_turns;
+        match game.play().winner {
+            Some(1) => 1.0,
+            Some(_) => 0.0,
+            None => evaluate(game.board(1), game.board(0)),
+        }
+    }
+}
+
+// Scores an unfinished game by how close each side is to winning
+fn evaluate(mine: &DeckBoard, opponent: &DeckBoard) -> f64 {
+    let progress = |board: &DeckBoard| f64::from(board.keys * KEY_COST + board.amber.min(KEY_COST));
+    let score = 0.5 + (progress(mine) - progress(opponent)) / f64::from(2 * KEYS_TO_WIN * KEY_COST);
+    score.clamp(0.0, 1.0)
+}
+
+impl Player for MctsPlayer {
+    fn choose_house(&self, view: &PlayerView) -> House {
+        let houses = view.mine.houses;
+        let deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        let mut rng = self.rng.borrow_mut();
+        let mut rewards = vec![(0.0, 0); houses.len()];
+        for i in 0..self.iterations.max(houses.len() as u32) as usize {
+            if self.out_of_time(deadline) {
+                break;
+            }
+            let index = i % houses.len();
+            let (mine, opponent) = view.sample(&mut *rng);
+            let policy = self.rollout.player(&mut rng);
+            let mut turn = Turn::resume(mine, opponent, houses[index].clone(), policy);
+            turn.start();
+            let reward = self.rollout(turn, &mut rng);
+            rewards[index].0 += reward;
+            rewards[index].1 += 1;
+        }
+
+        let mean = |&(total, count): &(f64, u32)| if count == 0 { 0.0 } else { total / f64::from(count) };
+        let best = (0..houses.len())
+            .max_by(|&a, &b| mean(&rewards[a]).partial_cmp(&mean(&rewards[b])).unwrap_or(Ordering::Equal))
+            .expect("Deck has no houses");
+        houses[best].clone()
+    }
+
+    fn next_action<'a, 'b>(&self, view: &PlayerView<'a, 'b>) -> Option<Action<'a>> {
+        if view.actions.is_empty() {
+            return None;
+        }
+        let house = view.house.as_ref().expect("Actions are only chosen once there is a house");
+        let deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        let mut rng = self.rng.borrow_mut();
+        let mut root = Node::new(None);
+        for _ in 0..self.iterations {
+            if self.out_of_time(deadline) {
+                break;
+            }
+            let (mine, opponent) = view.sample(&mut *rng);
+            let policy = self.rollout.player(&mut rng);
+            let sample = Turn::resume(mine, opponent, house.clone(), policy);
+            self.search(&mut root, sample, &mut rng);
+        }
+        root.children.iter()
+            .max_by_key(|c| c.visits)
+            .and_then(|c| c.action)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MctsPlayer, RolloutPolicy};
+    use api::test::deck_from;
+    use board::DeckBoard;
+    use game::test::test_card;
+    use game::{Flank, House, Type};
+    use player::Player;
+    use player::test::TestPlayer;
+    use turn::{Action, Turn};
+
+    #[test]
+    fn test_reaps_for_the_win() {
+        let deck = deck_from(test_card(House::Brobnar, Type::Creature, 0, 2, 0), 36);
+        let other = deck_from(test_card(House::Dis, Type::Creature, 0, 5, 0), 36);
+        let mut mine = DeckBoard::new(&deck);
+        let mut opponent = DeckBoard::new(&other);
+        for board in [&mut mine, &mut opponent].iter_mut() {
+            let card = board.hand[0];
+            board.play(card, true, Flank::Right);
+            board.my_turn_over();
+            board.hand.clear();
+            board.keys = 2;
+            board.amber = 5;
+        }
+
+        // Reaping now forges the last key before the opponent can, while
+        // fighting loses our only creature
+        let turn = Turn::resume(mine, opponent, House::Brobnar, Box::new(TestPlayer { house: House::Brobnar }));
+        let player = MctsPlayer::seeded(200, RolloutPolicy::Greedy, 7);
+        assert_eq!(player.next_action(&turn.view()), Some(Action::Reap(0)));
+    }
+
+    #[test]
+    fn test_no_actions_ends_turn() {
+        let deck = deck_from(test_card(House::Brobnar, Type::Creature, 0, 2, 0), 36);
+        let turn = Turn::resume(DeckBoard::new(&deck), DeckBoard::new(&deck), House::Dis,
+                                Box::new(TestPlayer { house: House::Dis }));
+        let player = MctsPlayer::seeded(10, RolloutPolicy::Random, 7);
+        assert_eq!(player.next_action(&turn.view()), None);
+        assert_eq!(player.choose_house(&turn.view()), House::Brobnar);
+    }
+}
@@ -0,0 +1,238 @@
+use turn::{Action, Choice, Decision, Target};
+use trigger::Side;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use game::House;
+use trigger::Trigger;
+use view::PlayerView;
+use std::cell::RefCell;
+use std::str::FromStr;
+use self::external::ExternalPlayer;
+use self::greedy::GreedyPlayer;
+use self::human::HumanPlayer;
+use self::lua::LuaPlayer;
+use self::mcts::{MctsPlayer, RolloutPolicy};
+
+pub mod external;
+pub mod greedy;
+pub mod human;
+pub mod lua;
+pub mod mcts;
+
+pub trait Player {
+    fn choose_house(&self, view: &PlayerView) -> House;
+    fn next_action<'a, 'b>(&self, view: &PlayerView<'a, 'b>) -> Option<Action<'a>>;
+
+    // Like next_action, but can also ask to undo or redo an action. Players
+    // that do should say so with can_undo.
+    fn next_decision<'a, 'b>(&self, view: &PlayerView<'a, 'b>) -> Decision<'a> {
+        match self.next_action(view) {
+            Some(action) => Decision::Act(action),
+            None => Decision::EndTurn,
+        }
+    }
+
+    // Whether the turn should keep what it needs to undo this player's actions
+    fn can_undo(&self) -> bool {
+        false
+    }
+
+    // Called when several triggers go off at once. Returns the indices of
+    // `triggers` in the order they should resolve.
+    fn order_triggers(&self, _view: &PlayerView, triggers: &[Trigger]) -> Vec<usize> {
+        (0..triggers.len()).collect()
+    }
+
+    // Called when a card needs a target, e.g. for its bonus icons. Returns the
+    // index in `targets` to pick, or None to pass when the choice allows it.
+    fn choose_target(&self, view: &PlayerView, choice: Choice, targets: &[Target]) -> Option<usize> {
+        default_target(view, choice, targets)
+    }
+
+    // Called once with the opening hand, before the first turn. Returns true
+    // to shuffle it back into the deck and draw one card fewer.
+    fn mulligan(&self, _view: &PlayerView) -> bool {
+        false
+    }
+}
+
+// Damage goes to the enemy creature closest to dying, captured amber to the
+// first creature offered, control is taken of the strongest creature, and
+// cards that can't be played this turn are discarded
+pub fn default_target(view: &PlayerView, choice: Choice, targets: &[Target]) -> Option<usize> {
+    let creature = |target: &Target| match *target {
+        Target::Creature(Side::Mine, index) => view.mine.creatures.get(index),
+        Target::Creature(Side::Opponent, index) => view.opponent.creatures.get(index),
+        _ => None,
+    };
+    let enemy = |target: &Target| match *target {
+        Target::Creature(side, _) | Target::Artifact(side, _) => side == Side::Opponent,
+        Target::Hand(_) => false,
+    };
+    match choice {
+        Choice::Capture => Some(0),
+        Choice::Damage => {
+            let health = |index: &usize| creature(&targets[*index])
+                .map_or(0, |c| c.power().saturating_sub(c.damage) + c.armor);
+            // Friendly creatures are only hurt when there is nothing else
+            let (enemies, friends): (Vec<usize>, Vec<usize>) = (0..targets.len()).partition(|&index| enemy(&targets[index]));
+            enemies.into_iter().min_by_key(health).or_else(|| friends.into_iter().max_by_key(health))
+        }
+        Choice::Discard => targets.iter().position(|target| match *target {
+            Target::Hand(index) => view.hand.get(index).is_some_and(|card| Some(&card.house) != view.house.as_ref()),
+            _ => false,
+        }),
+        Choice::TakeControl => {
+            (0..targets.len()).max_by_key(|&index| creature(&targets[index]).map_or(0, |c| c.power()))
+        }
+    }
+}
+
+// Picks uniformly between its houses, and between every legal action and
+// ending the turn
+pub struct RandomPlayer {
+    rng: RefCell<StdRng>,
+}
+
+impl RandomPlayer {
+    pub fn seeded(seed: u64) -> Self {
+        RandomPlayer {
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl Player for RandomPlayer {
+    fn choose_house(&self, view: &PlayerView) -> House {
+        view.mine.houses.choose(&mut *self.rng.borrow_mut()).expect("Deck has no houses").clone()
+    }
+
+    fn next_action<'a, 'b>(&self, view: &PlayerView<'a, 'b>) -> Option<Action<'a>> {
+        let choice = self.rng.borrow_mut().gen_range(0, view.actions.len() + 1);
+        view.actions.get(choice).cloned()
+    }
+
+    fn choose_target(&self, _view: &PlayerView, _choice: Choice, targets: &[Target]) -> Option<usize> {
+        // Passing, where allowed, is one more option
+        Some(self.rng.borrow_mut().gen_range(0, targets.len() + 1))
+    }
+}
+
+// MCTS iterations per decision when none are given
+pub const DEFAULT_MCTS_ITERATIONS: u32 = 200;
+
+// The kinds of player that can be picked by name, e.g. from the command line:
+//
+//   random, greedy, human
+//   mcts, mcts:<iterations> or mcts:<iterations>:<rollout>, where the
+//   rollout is greedy (the default) or random
+//   lua:<script path>
+//   external:<command line>
+#[derive(Debug, PartialEq, Clone)]
+pub enum PlayerKind {
+    Random,
+    Greedy,
+    Mcts(u32, RolloutPolicy),
+    Lua(String),
+    External(String),
+    Human,
+}
+
+impl FromStr for PlayerKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let (kind, arg) = match name.find(':') {
+            Some(index) => (&name[..index], Some(&name[index + 1..])),
+            None => (name, None),
+        };
+        match (kind, arg) {
+            ("random", None) => Ok(PlayerKind::Random),
+            ("greedy", None) => Ok(PlayerKind::Greedy),
+            ("human", None) => Ok(PlayerKind::Human),
+            ("mcts", None) => Ok(PlayerKind::Mcts(DEFAULT_MCTS_ITERATIONS, RolloutPolicy::Greedy)),
+            ("mcts", Some(arg)) => {
+                let (iterations, rollout) = match arg.find(':') {
+                    Some(index) => (&arg[..index], &arg[index + 1..]),
+                    None => (arg, "greedy"),
+                };
+                let iterations = iterations.parse()
+                    .map_err(|_| format!("Bad MCTS iteration count: {}", iterations))?;
+                let rollout = match rollout {
+                    "greedy" => RolloutPolicy::Greedy,
+                    "random" => RolloutPolicy::Random,
+                    _ => return Err(format!("Unknown MCTS rollout: {}", rollout)),
+                };
+                Ok(PlayerKind::Mcts(iterations, rollout))
+            }
+            ("lua", Some(path)) if !path.is_empty() => Ok(PlayerKind::Lua(path.to_string())),
+            ("external", Some(command)) if !command.trim().is_empty() => {
+                Ok(PlayerKind::External(command.to_string()))
+            }
+            _ => Err(format!("Unknown player: {}", name)),
+        }
+    }
+}
+
+impl PlayerKind {
+    // Players that make random choices are seeded so games can be replayed
+    pub fn create(&self, seed: u64) -> Result<Box<dyn Player>, String> {
+        Ok(match *self {
+            PlayerKind::Random => Box::new(RandomPlayer::seeded(seed)),
+            PlayerKind::Greedy => Box::new(GreedyPlayer),
+            PlayerKind::Mcts(iterations, rollout) => Box::new(MctsPlayer::seeded(iterations, rollout, seed)),
+            PlayerKind::Lua(ref path) => Box::new(LuaPlayer::from_file(path)
+                .map_err(|err| format!("Couldn't load {}: {}", path, err))?),
+            PlayerKind::External(ref command) => {
+                let mut words = command.split_whitespace();
+                let program = words.next().expect("Command is not empty");
+                let args: Vec<&str> = words.collect();
+                Box::new(ExternalPlayer::spawn(program, &args)
+                    .map_err(|err| format!("Couldn't start {}: {}", command, err))?)
+            }
+            PlayerKind::Human => Box::new(HumanPlayer::stdio()),
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use player::Player;
+    use turn::Action;
+    use game::House;
+    use view::PlayerView;
+
+    pub struct TestPlayer {
+        pub house: House,
+    }
+
+    impl Player for TestPlayer {
+        fn choose_house(&self, _view: &PlayerView) -> House {
+            self.house.clone()
+        }
+
+        fn next_action<'a, 'b>(&self, _view: &PlayerView<'a, 'b>) -> Option<Action<'a>> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_parse_player_kind() {
+        use player::{PlayerKind, DEFAULT_MCTS_ITERATIONS};
+        use player::mcts::RolloutPolicy;
+
+        assert_eq!("greedy".parse(), Ok(PlayerKind::Greedy));
+        assert_eq!("mcts".parse(), Ok(PlayerKind::Mcts(DEFAULT_MCTS_ITERATIONS, RolloutPolicy::Greedy)));
+        assert_eq!("mcts:50".parse(), Ok(PlayerKind::Mcts(50, RolloutPolicy::Greedy)));
+        assert_eq!("mcts:50:random".parse(), Ok(PlayerKind::Mcts(50, RolloutPolicy::Random)));
+        assert_eq!("lua:bots/ai.lua".parse(), Ok(PlayerKind::Lua("bots/ai.lua".to_string())));
+        assert_eq!("external:python3 bot.py".parse(), Ok(PlayerKind::External("python3 bot.py".to_string())));
+        assert!("mcts:lots".parse::<PlayerKind>().is_err());
+        assert!("mcts:50:clever".parse::<PlayerKind>().is_err());
+        assert!("lua:".parse::<PlayerKind>().is_err());
+        assert!("greedy:1".parse::<PlayerKind>().is_err());
+        assert!("clever".parse::<PlayerKind>().is_err());
+    }
+}
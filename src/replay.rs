@@ -0,0 +1,409 @@
+// Game logs and replays.
+//
+// A log is every event of a game in order, one JSON object per line in a
+// replay file. Seats are 0 for whoever went first and 1 for the other player,
+// creature indices are positions on the board at the time, and cards are
+// their ids.
+//
+// The decisions in a log (house choices, actions, trigger orders) together
+// with each deck's shuffle seed are enough to play the game again. Everything
+// else - draws, damage, destructions, forges - is a consequence, and replaying
+// checks that the engine still produces exactly those.
+
+use api::Deck;
+use board::{DeckBoard, KEYS_TO_WIN};
+use game::{CardDetails, Flank, House};
+use player::Player;
+use serde_json;
+use snapshot::GameSnapshot;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use trigger::Trigger;
+use turn::{Action, Choice, Target, Turn};
+use view::PlayerView;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LogEvent {
+    // `deck` is the deck id or path the game was set up with
+    Start { seat: usize, deck: String, seed: u64 },
+    Draw { seat: usize, card: String },
+    // The opening hand went back into the deck, the draws that follow are
+    // the new one
+    Mulligan { seat: usize },
+    Forge { seat: usize, keys: u32 },
+    ChooseHouse { seat: usize, house: House },
+    // `slot` tells copies of the card apart
+    Play { seat: usize, card: String, slot: usize, flank: Flank },
+    Reap { seat: usize, creature: usize },
+    Fight { seat: usize, creature: usize, target: usize },
+    // `seat` is the side of the table the creature is on
+    Damage { seat: usize, creature: usize, amount: u32 },
+    // `by` is the creature that destroyed it in a fight
+    Destroyed { seat: usize, creature: usize, card: String, by: Option<String> },
+    // Amber gained from a card: its bonus, reaping or its abilities
+    Amber { seat: usize, card: String, amount: u32 },
+    // A card put into the seat's discard pile
+    Discard { seat: usize, card: String },
+    // A card that moved to the seat's side of the table
+    Control { seat: usize, card: String },
+    OrderTriggers { seat: usize, order: Vec<usize> },
+    // Which of the targets on offer was picked, None if the player passed
+    ChooseTarget { seat: usize, target: Option<usize> },
+    EndTurn { seat: usize },
+    GameOver { winner: Option<usize>, turns: u32 },
+}
+
+impl LogEvent {
+    pub fn draw(seat: usize, card: &CardDetails) -> Self {
+        LogEvent::Draw { seat, card: card.id.clone() }
+    }
+}
+
+// How a game log begins: both decks, then both opening hands
+pub fn start_events(names: &[String; 2], boards: [&DeckBoard; 2]) -> Vec<LogEvent> {
+    let mut events = Vec::new();
+    for (seat, (name, board)) in names.iter().zip(&boards).enumerate() {
+        events.push(LogEvent::Start { seat, deck: name.clone(), seed: board.seed });
+    }
+    for (seat, board) in boards.iter().enumerate() {
+        events.extend(board.hand.iter().map(|card| LogEvent::draw(seat, card)));
+    }
+    events
+}
+
+pub fn write_log<W: Write>(mut out: W, events: &[LogEvent]) -> io::Result<()> {
+    for event in events {
+        let line = serde_json::to_string(event).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()
+}
+
+pub fn read_log<R: BufRead>(input: R) -> Result<Vec<LogEvent>, String> {
+    let mut events = Vec::new();
+    for (number, line) in input.lines().enumerate() {
+        let line = line.map_err(|err| err.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line).map_err(|err| format!("Line {}: {}", number + 1, err))?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+// The decks a log was recorded with, by seat
+pub fn deck_names(events: &[LogEvent]) -> Result<[String; 2], String> {
+    match (events.first(), events.get(1)) {
+        (Some(&LogEvent::Start { seat: 0, deck: ref first, .. }),
+         Some(&LogEvent::Start { seat: 1, deck: ref second, .. })) => Ok([first.clone(), second.clone()]),
+        _ => Err("A log starts with both decks".to_string()),
+    }
+}
+
+// Orders triggers the way the log says. Its other decisions are made by the
+// replayer directly.
+struct ScriptedPlayer {
+    orders: RefCell<VecDeque<Vec<usize>>>,
+    targets: RefCell<VecDeque<Option<usize>>>,
+}
+
+impl Player for ScriptedPlayer {
+    fn choose_house(&self, view: &PlayerView) -> House {
+        view.mine.houses[0].clone()
+    }
+
+    fn next_action<'a, 'b>(&self, _view: &PlayerView<'a, 'b>) -> Option<Action<'a>> {
+        None
+    }
+
+    fn order_triggers(&self, _view: &PlayerView, triggers: &[Trigger]) -> Vec<usize> {
+        // A missing order shows up as a mismatch with the log
+        self.orders.borrow_mut().pop_front().unwrap_or_else(|| (0..triggers.len()).collect())
+    }
+
+    fn choose_target(&self, _view: &PlayerView, _choice: Choice, _targets: &[Target]) -> Option<usize> {
+        self.targets.borrow_mut().pop_front().unwrap_or(None)
+    }
+}
+
+// Plays a log back one decision at a time, checking the engine's events
+// against it as it goes
+pub struct Replayer<'a> {
+    events: Vec<LogEvent>,
+    // Events matched so far
+    position: usize,
+    // By seat, while no turn is in progress
+    boards: [Option<DeckBoard<'a>>; 2],
+    turn: Option<Turn<'a>>,
+    active: usize,
+    turns: u32,
+}
+
+impl<'a> Replayer<'a> {
+    // `decks` are by seat, see `deck_names`
+    pub fn new(decks: [&'a Deck; 2], events: Vec<LogEvent>) -> Result<Self, String> {
+        let names = deck_names(&events)?;
+        let seed = |seat: usize| match events[seat] {
+            LogEvent::Start { seed, .. } => seed,
+            _ => unreachable!(),
+        };
+        let first = DeckBoard::seeded(decks[0], seed(0));
+        let second = DeckBoard::seeded(decks[1], seed(1));
+        let opening = start_events(&names, [&first, &second]);
+        let mut replayer = Replayer {
+            events,
+            position: 0,
+            boards: [Some(first), Some(second)],
+            turn: None,
+            active: 0,
+            turns: 0,
+        };
+        replayer.check(opening)?;
+        Ok(replayer)
+    }
+
+    // How many events have been replayed
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn board(&self, seat: usize) -> &DeckBoard<'a> {
+        match self.turn {
+            Some(ref turn) if seat == self.active => turn.mine(),
+            Some(ref turn) => turn.opponent(),
+            None => self.boards[seat].as_ref().unwrap(),
+        }
+    }
+
+    // The house being played, if a turn is in progress
+    pub fn house(&self) -> Option<House> {
+        self.turn.as_ref().map(|turn| turn.house())
+    }
+
+    // The game as it stands after the events replayed so far
+    pub fn snapshot(&self) -> GameSnapshot {
+        let names = deck_names(&self.events).expect("Checked when the replayer was made");
+        GameSnapshot::new(&names, [self.board(0), self.board(1)], self.active, self.turns, self.house())
+    }
+
+    // Matches what the engine did against the log
+    fn check(&mut self, produced: Vec<LogEvent>) -> Result<(), String> {
+        for event in produced {
+            match self.events.get(self.position) {
+                Some(logged) if *logged == event => self.position += 1,
+                logged => {
+                    return Err(format!("Event {}: the log has {:?} but the replay did {:?}",
+                                       self.position, logged, event));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn unexpected(&self) -> String {
+        format!("Event {}: didn't expect {:?}", self.position, self.events[self.position])
+    }
+
+    // Replays the next decision and everything that followed from it.
+    // Returns false once the log is used up.
+    pub fn step(&mut self) -> Result<bool, String> {
+        let event = match self.events.get(self.position) {
+            Some(event) => event.clone(),
+            None => return Ok(false),
+        };
+        let produced = match (event, self.turn.is_some()) {
+            (LogEvent::Forge { .. }, false) | (LogEvent::ChooseHouse { .. }, false) => self.begin_turn()?,
+            // The first player's seventh card, before anyone mulligans
+            (LogEvent::Draw { seat: 0, .. }, false) if self.turns == 0 => {
+                let drawn = self.boards[0].as_mut().unwrap().draw_to(7);
+                drawn.into_iter().map(|card| LogEvent::draw(0, &card)).collect()
+            }
+            (LogEvent::Mulligan { seat }, false) if self.turns == 0 && seat < 2 => {
+                let drawn = self.boards[seat].as_mut().unwrap().mulligan();
+                let mut produced = vec![LogEvent::Mulligan { seat }];
+                produced.extend(drawn.into_iter().map(|card| LogEvent::draw(seat, &card)));
+                produced
+            }
+            (LogEvent::GameOver { winner, turns }, false) => {
+                let won = winner.is_none_or(|seat| self.board(seat).keys >= KEYS_TO_WIN);
+                if !won || turns != self.turns {
+                    return Err(self.unexpected());
+                }
+                vec![LogEvent::GameOver { winner, turns }]
+            }
+            (LogEvent::Play { ref card, slot, flank, .. }, true) => {
+                let card = self.turn.as_ref().unwrap().mine().hand.iter()
+                    .find(|c| c.id == *card && c.slot == slot)
+                    .cloned()
+                    .ok_or_else(|| self.unexpected())?;
+                self.act(Action::Play(card, flank))?
+            }
+            (LogEvent::Reap { creature, .. }, true) => self.act(Action::Reap(creature))?,
+            (LogEvent::Fight { creature, target, .. }, true) => self.act(Action::Fight(creature, target))?,
+            (LogEvent::EndTurn { .. }, true) => self.end_turn(),
+            _ => return Err(self.unexpected()),
+        };
+        self.check(produced)?;
+        Ok(true)
+    }
+
+    // Replays the first `count` events, or as far as the log goes
+    pub fn run_to(&mut self, count: usize) -> Result<(), String> {
+        while self.position < count && self.step()? {}
+        Ok(())
+    }
+
+    // Replays the whole log
+    pub fn verify(&mut self) -> Result<(), String> {
+        let count = self.events.len();
+        self.run_to(count)
+    }
+
+    fn begin_turn(&mut self) -> Result<Vec<LogEvent>, String> {
+        let seat = self.active;
+        let mut produced = Vec::new();
+        let mut active = self.boards[seat].take().unwrap();
+        if active.forge_key() {
+            produced.push(LogEvent::Forge { seat, keys: active.keys });
+            if active.keys >= KEYS_TO_WIN {
+                self.turns += 1;
+                self.boards[seat] = Some(active);
+                return Ok(produced);
+            }
+        }
+
+        let house = match self.events.get(self.position + produced.len()) {
+            Some(LogEvent::ChooseHouse { house, .. }) => house.clone(),
+            _ => {
+                self.boards[seat] = Some(active);
+                return Err(self.unexpected());
+            }
+        };
+        produced.push(LogEvent::ChooseHouse { seat, house: house.clone() });
+
+        // The trigger orders and targets this turn will need
+        let this_turn = || self.events[self.position..].iter()
+            .take_while(|event| **event != LogEvent::EndTurn { seat });
+        let orders = this_turn()
+            .filter_map(|event| match *event {
+                LogEvent::OrderTriggers { ref order, .. } => Some(order.clone()),
+                _ => None,
+            })
+            .collect();
+        let targets = this_turn()
+            .filter_map(|event| match *event {
+                LogEvent::ChooseTarget { target, .. } => Some(target),
+                _ => None,
+            })
+            .collect();
+        let player = ScriptedPlayer { orders: RefCell::new(orders), targets: RefCell::new(targets) };
+
+        let waiting = self.boards[1 - seat].take().unwrap();
+        let mut turn = Turn::resume(active, waiting, house, Box::new(player));
+        turn.record(seat);
+        turn.start();
+        produced.extend(turn.take_events());
+        self.turn = Some(turn);
+        Ok(produced)
+    }
+
+    fn act(&mut self, action: Action<'a>) -> Result<Vec<LogEvent>, String> {
+        let turn = self.turn.as_mut().unwrap();
+        if !turn.legal_actions().contains(&action) {
+            return Err(format!("Event {}: {:?} isn't legal here", self.position, action));
+        }
+        turn.execute_action(action);
+        Ok(turn.take_events())
+    }
+
+    fn end_turn(&mut self) -> Vec<LogEvent> {
+        let mut turn = self.turn.take().unwrap();
+        turn.end();
+        let produced = turn.take_events();
+        let (mine, opponent, _) = turn.finish();
+        self.boards[self.active] = Some(mine);
+        self.boards[1 - self.active] = Some(opponent);
+        self.active = 1 - self.active;
+        self.turns += 1;
+        produced
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_log, write_log, LogEvent, Replayer};
+    use api::test::deck_from;
+    use board::DeckBoard;
+    use game::test::test_card;
+    use game::{House, Type};
+    use player::RandomPlayer;
+    use simulation::Game;
+    use std::io::Cursor;
+
+    fn names() -> [String; 2] {
+        ["a".to_string(), "b".to_string()]
+    }
+
+    #[test]
+    fn test_replay_matches_game() {
+        let deck_a = deck_from(test_card(House::Brobnar, Type::Creature, 1, 3, 0), 36);
+        let deck_b = deck_from(test_card(House::Dis, Type::Creature, 1, 2, 1), 36);
+        let mut game = Game::new(DeckBoard::seeded(&deck_a, 1), DeckBoard::seeded(&deck_b, 2),
+                                 Box::new(RandomPlayer::seeded(3)), Box::new(RandomPlayer::seeded(4)));
+        game.record(names());
+        game.start();
+        let result = game.play();
+        let events = game.events().unwrap().to_vec();
+        assert_eq!(events.last(), Some(&LogEvent::GameOver { winner: result.winner, turns: result.turns }));
+        assert!(events.iter().any(|e| matches!(*e, LogEvent::Fight { .. })));
+        assert!(events.iter().any(|e| matches!(*e, LogEvent::Damage { .. })));
+
+        // Through a replay file and back
+        let mut file = Vec::new();
+        write_log(&mut file, &events).unwrap();
+        let events = read_log(Cursor::new(file)).unwrap();
+
+        let mut replayer = Replayer::new([&deck_a, &deck_b], events.clone()).unwrap();
+        replayer.verify().unwrap();
+        assert_eq!(replayer.position(), events.len());
+        for seat in 0..2 {
+            let board = replayer.board(seat);
+            let original = game.board(seat);
+            assert_eq!(board.amber, original.amber);
+            assert_eq!(board.keys, original.keys);
+            assert_eq!(board.hand, original.hand);
+            assert_eq!(board.deck, original.deck);
+            assert_eq!(board.creatures.len(), original.creatures.len());
+        }
+
+        // Part way through
+        let mut replayer = Replayer::new([&deck_a, &deck_b], events.clone()).unwrap();
+        replayer.run_to(40).unwrap();
+        assert!(replayer.position() >= 40 && replayer.position() < events.len());
+    }
+
+    #[test]
+    fn test_replay_catches_tampering() {
+        let deck = deck_from(test_card(House::Brobnar, Type::Creature, 1, 3, 0), 36);
+        let mut game = Game::new(DeckBoard::seeded(&deck, 1), DeckBoard::seeded(&deck, 2),
+                                 Box::new(RandomPlayer::seeded(3)), Box::new(RandomPlayer::seeded(4)));
+        game.record(names());
+        game.play();
+        let mut events = game.events().unwrap().to_vec();
+
+        // Claim a fight did more damage than it did
+        let index = events.iter()
+            .position(|e| matches!(*e, LogEvent::Damage { .. }))
+            .unwrap();
+        if let LogEvent::Damage { ref mut amount, .. } = events[index] {
+            *amount += 1;
+        }
+        let mut replayer = Replayer::new([&deck, &deck], events).unwrap();
+        let err = replayer.verify().unwrap_err();
+        assert!(err.starts_with(&format!("Event {}:", index)), "{}", err);
+        assert_eq!(replayer.position(), index);
+    }
+}
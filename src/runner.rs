@@ -0,0 +1,82 @@
+use simulation::{play_series_game, MatchStats, Series};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+// Plays the games of a series spread over `threads` workers. Every game is
+// seeded from the series seed and its own number, so the totals are the same
+// whatever the thread count. The first error stops every worker.
+pub fn run_series(series: &Series, threads: usize) -> Result<MatchStats, String> {
+    let next_game = Arc::new(AtomicUsize::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let workers: Vec<_> = (0..threads.max(1)).map(|_| {
+        let series = series.clone();
+        let next_game = next_game.clone();
+        let stop = stop.clone();
+        thread::spawn(move || -> Result<MatchStats, String> {
+            let mut stats = MatchStats::default();
+            loop {
+                if stop.load(Ordering::SeqCst) {
+                    return Ok(stats);
+                }
+                let game = next_game.fetch_add(1, Ordering::SeqCst);
+                if game >= series.games as usize {
+                    return Ok(stats);
+                }
+                match play_series_game(&series, game as u32) {
+                    Ok(result) => stats.add(&result),
+                    Err(err) => {
+                        stop.store(true, Ordering::SeqCst);
+                        return Err(err);
+                    }
+                }
+            }
+        })
+    }).collect();
+
+    let mut total = MatchStats::default();
+    let mut error = None;
+    for worker in workers {
+        match worker.join() {
+            Ok(Ok(stats)) => total.merge(&stats),
+            Ok(Err(err)) => error = Some(err),
+            Err(_) => error = Some("A simulation thread panicked".to_string()),
+        }
+    }
+    match error {
+        Some(err) => Err(err),
+        None => Ok(total),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::run_series;
+    use api::test::deck_from;
+    use game::test::test_card;
+    use game::{House, Type};
+    use player::PlayerKind;
+    use simulation::Series;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_same_results_on_any_thread_count() {
+        let decks = [
+            Arc::new(deck_from(test_card(House::Brobnar, Type::Creature, 1, 3, 0), 36)),
+            Arc::new(deck_from(test_card(House::Dis, Type::Creature, 1, 2, 0), 36)),
+        ];
+        let series = Series::new(decks, [PlayerKind::Random, PlayerKind::Greedy], 12, 42);
+        let stats = run_series(&series, 1).unwrap();
+        assert_eq!(stats.games, 12);
+        assert_eq!(stats.wins[0] + stats.wins[1] + stats.draws, 12);
+        assert_eq!(run_series(&series, 4).unwrap(), stats);
+        assert_eq!(run_series(&series, 20).unwrap(), stats);
+    }
+
+    #[test]
+    fn test_player_errors_are_reported() {
+        let deck = Arc::new(deck_from(test_card(House::Brobnar, Type::Creature, 1, 3, 0), 36));
+        let kinds = [PlayerKind::Greedy, PlayerKind::Lua("no/such/script.lua".to_string())];
+        assert!(run_series(&Series::new([deck.clone(), deck], kinds, 4, 1), 2).is_err());
+    }
+}
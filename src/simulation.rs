@@ -0,0 +1,511 @@
+use api::Deck;
+use board::{DeckBoard, KEYS_TO_WIN};
+use game::House;
+use houses::HouseStats;
+use impact::ImpactStats;
+use player::{Player, PlayerKind};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use replay::{self, LogEvent};
+use snapshot::GameSnapshot;
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::Arc;
+use timeline::{Timeline, TimelineStats, TurnStats};
+use turn::Turn;
+use view::PlayerView;
+
+// Games that go on longer than this are called a draw
+pub const MAX_TURNS: u32 = 200;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct GameResult {
+    // Seat of the winner, 0 for whoever went first, None for a draw
+    pub winner: Option<usize>,
+    pub turns: u32,
+    // Which seats took a mulligan
+    pub mulligans: [bool; 2],
+}
+
+pub struct Game<'a> {
+    // Always ordered [active, waiting]
+    boards: Vec<DeckBoard<'a>>,
+    players: Vec<Box<dyn Player>>,
+    // Seat of the active player
+    active: usize,
+    turns: u32,
+    pub max_turns: u32,
+    mulligans: [bool; 2],
+    timeline: Timeline,
+    // The house of a turn that was loaded part way through
+    resumed_house: Option<House>,
+    // Everything that happened so far, when recording
+    log: Option<Vec<LogEvent>>,
+}
+
+impl<'a> Game<'a> {
+    // Seat 0 takes the first turn
+    pub fn new(first: DeckBoard<'a>, second: DeckBoard<'a>,
+               first_player: Box<dyn Player>, second_player: Box<dyn Player>) -> Self {
+        Game {
+            boards: vec![first, second],
+            players: vec![first_player, second_player],
+            active: 0,
+            turns: 0,
+            max_turns: MAX_TURNS,
+            mulligans: [false, false],
+            timeline: Timeline::new(),
+            resumed_house: None,
+            log: None,
+        }
+    }
+
+    // Picks up a saved game. `decks` and the players are by seat.
+    pub fn resume(snapshot: &GameSnapshot, decks: [&'a Deck; 2],
+                  first_player: Box<dyn Player>, second_player: Box<dyn Player>) -> Result<Self, String> {
+        let (first, second) = snapshot.load(decks)?;
+        let mut game = Game::new(first, second, first_player, second_player);
+        if snapshot.active == 1 {
+            game.boards.reverse();
+            game.players.reverse();
+        }
+        game.active = snapshot.active;
+        game.turns = snapshot.turns;
+        game.resumed_house = snapshot.house.clone();
+        Ok(game)
+    }
+
+    // `names` are what each seat's deck is called
+    #[cfg(test)]
+    pub fn snapshot(&self, names: &[String; 2]) -> GameSnapshot {
+        GameSnapshot::new(names, [self.board(0), self.board(1)], self.active, self.turns, self.resumed_house.clone())
+    }
+
+    // Starts logging events. `decks` are what each seat's deck is called.
+    pub fn record(&mut self, decks: [String; 2]) {
+        self.log = Some(replay::start_events(&decks, [self.board(0), self.board(1)]));
+    }
+
+    pub fn events(&self) -> Option<&[LogEvent]> {
+        self.log.as_deref()
+    }
+
+    fn log(&mut self, event: LogEvent) {
+        if let Some(ref mut log) = self.log {
+            log.push(event);
+        }
+    }
+
+    pub fn board(&self, seat: usize) -> &DeckBoard<'a> {
+        if seat == self.active {
+            &self.boards[0]
+        } else {
+            &self.boards[1]
+        }
+    }
+
+    #[cfg(test)]
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    #[cfg(test)]
+    pub fn turns(&self) -> u32 {
+        self.turns
+    }
+
+    // Deals the opening hands: the first player draws up to 7, then each
+    // player in turn may mulligan. Boards come with 6 cards already drawn.
+    pub fn start(&mut self) {
+        assert_eq!(self.turns, 0, "Opening hands are dealt before the first turn");
+        for card in self.boards[self.active].draw_to(7) {
+            self.log(LogEvent::draw(0, &card));
+        }
+        for seat in 0..2 {
+            // Seat 0 is active before the first turn
+            let view = PlayerView::choosing(&self.boards[seat], &self.boards[1 - seat]);
+            if !self.players[seat].mulligan(&view) {
+                continue;
+            }
+            self.mulligans[seat] = true;
+            self.log(LogEvent::Mulligan { seat });
+            for card in self.boards[seat].mulligan() {
+                self.log(LogEvent::draw(seat, &card));
+            }
+        }
+    }
+
+    // Plays a single turn for the active player. Returns the winner's seat if
+    // the game is over.
+    pub fn play_turn(&mut self) -> Option<usize> {
+        let waiting_player = self.players.pop().unwrap();
+        let active_player = self.players.pop().unwrap();
+        let waiting = self.boards.pop().unwrap();
+        let mut active = self.boards.pop().unwrap();
+
+        let seat = self.active;
+        let resumed_house = self.resumed_house.take();
+        let forged = resumed_house.is_none() && active.forge_key();
+        if forged {
+            self.log(LogEvent::Forge { seat, keys: active.keys });
+        }
+        let won = forged && active.keys >= KEYS_TO_WIN;
+        let (active, waiting, active_player, played) = if won {
+            (active, waiting, active_player, 0)
+        } else {
+            let mut turn = match resumed_house {
+                Some(ref house) => Turn::resume(active, waiting, house.clone(), active_player),
+                None => Turn::new(active, waiting, active_player),
+            };
+            if self.log.is_some() {
+                self.log(LogEvent::ChooseHouse { seat, house: turn.house() });
+                turn.record(seat);
+            }
+            if resumed_house.is_none() {
+                turn.start();
+            }
+            turn.run();
+            turn.end();
+            let events = turn.take_events();
+            if let Some(ref mut log) = self.log {
+                log.extend(events);
+            }
+            let played = turn.cards_played();
+            let (active, waiting, active_player) = turn.finish();
+            (active, waiting, active_player, played)
+        };
+        self.turns += 1;
+        self.timeline.push(TurnStats::new(seat, &active, played));
+
+        if won {
+            self.boards = vec![active, waiting];
+            self.players = vec![active_player, waiting_player];
+            return Some(self.active);
+        }
+
+        // Hand over to the other player
+        self.boards = vec![waiting, active];
+        self.players = vec![waiting_player, active_player];
+        self.active = 1 - self.active;
+        None
+    }
+
+    pub fn play(&mut self) -> GameResult {
+        while self.turns < self.max_turns {
+            if let Some(winner) = self.play_turn() {
+                return self.over(Some(winner));
+            }
+        }
+        self.over(None)
+    }
+
+    fn over(&mut self, winner: Option<usize>) -> GameResult {
+        let turns = self.turns;
+        self.log(LogEvent::GameOver { winner, turns });
+        GameResult { winner, turns, mulligans: self.mulligans }
+    }
+}
+
+// Results of a series of games between two decks, A (side 0) and B (side 1)
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct MatchStats {
+    pub games: u32,
+    pub wins: [u32; 2],
+    pub draws: u32,
+    // Decided games won by whoever took the first turn
+    pub first_player_wins: u32,
+    pub total_turns: u64,
+    // Games where the side took a mulligan, and how many of those it won
+    pub mulligans: [u32; 2],
+    pub mulligan_wins: [u32; 2],
+    pub timeline: TimelineStats,
+    pub cards: [ImpactStats; 2],
+    pub houses: [HouseStats; 2],
+}
+
+impl MatchStats {
+    // `first` is the side that took the first turn
+    pub fn record(&mut self, result: &GameResult, first: usize) {
+        self.games += 1;
+        self.total_turns += u64::from(result.turns);
+        // Seat 0 is whoever went first
+        let side = |seat| if seat == 0 { first } else { 1 - first };
+        match result.winner {
+            Some(seat) => {
+                self.wins[side(seat)] += 1;
+                if seat == 0 {
+                    self.first_player_wins += 1;
+                }
+            }
+            None => self.draws += 1,
+        }
+        for seat in 0..2 {
+            if result.mulligans[seat] {
+                self.mulligans[side(seat)] += 1;
+                if result.winner == Some(seat) {
+                    self.mulligan_wins[side(seat)] += 1;
+                }
+            }
+        }
+    }
+
+    // Adds everything about one game of the series
+    pub fn add(&mut self, game: &SeriesGame) {
+        self.record(&game.result, game.first);
+        self.timeline.record(&game.timeline, game.first);
+        for seat in 0..2 {
+            let side = if seat == 0 { game.first } else { 1 - game.first };
+            let won = game.result.winner == Some(seat);
+            self.cards[side].record(&game.events, seat, won);
+            self.houses[side].record(&game.events, seat, won, &game.opening_hands[seat]);
+        }
+    }
+
+    pub fn merge(&mut self, other: &MatchStats) {
+        self.games += other.games;
+        self.wins[0] += other.wins[0];
+        self.wins[1] += other.wins[1];
+        self.draws += other.draws;
+        self.first_player_wins += other.first_player_wins;
+        self.total_turns += other.total_turns;
+        for side in 0..2 {
+            self.mulligans[side] += other.mulligans[side];
+            self.mulligan_wins[side] += other.mulligan_wins[side];
+        }
+        self.timeline.merge(&other.timeline);
+        for side in 0..2 {
+            self.cards[side].merge(&other.cards[side]);
+            self.houses[side].merge(&other.houses[side]);
+        }
+    }
+
+    fn rate(count: u32, out_of: u32) -> f64 {
+        if out_of == 0 {
+            0.0
+        } else {
+            f64::from(count) / f64::from(out_of)
+        }
+    }
+
+    pub fn win_rate(&self, side: usize) -> f64 {
+        MatchStats::rate(self.wins[side], self.games)
+    }
+
+    pub fn average_turns(&self) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.total_turns as f64 / f64::from(self.games)
+        }
+    }
+
+    // Share of games the side won after taking a mulligan
+    pub fn mulligan_win_rate(&self, side: usize) -> f64 {
+        MatchStats::rate(self.mulligan_wins[side], self.mulligans[side])
+    }
+
+    // Share of decided games that the first player won
+    pub fn first_player_win_rate(&self) -> f64 {
+        MatchStats::rate(self.first_player_wins, self.games - self.draws)
+    }
+}
+
+impl fmt::Display for MatchStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Games:         {}", self.games)?;
+        writeln!(f, "A wins:        {} ({:.1}%)", self.wins[0], 100.0 * self.win_rate(0))?;
+        writeln!(f, "B wins:        {} ({:.1}%)", self.wins[1], 100.0 * self.win_rate(1))?;
+        writeln!(f, "Draws:         {}", self.draws)?;
+        writeln!(f, "Average turns: {:.1}", self.average_turns())?;
+        writeln!(f, "Mulligans:     A {} (won {:.1}%), B {} (won {:.1}%)",
+                 self.mulligans[0], 100.0 * self.mulligan_win_rate(0),
+                 self.mulligans[1], 100.0 * self.mulligan_win_rate(1))?;
+        write!(f, "First player:  won {:.1}% of decided games", 100.0 * self.first_player_win_rate())
+    }
+}
+
+// The seed for one game of a series, so a game plays out the same whatever
+// else is run alongside it
+pub fn game_seed(seed: u64, game: u32) -> u64 {
+    StdRng::seed_from_u64(seed ^ u64::from(game).wrapping_mul(0x9e37_79b9_7f4a_7c15)).gen()
+}
+
+// A series of games between decks A and B
+#[derive(Clone)]
+pub struct Series {
+    pub decks: [Arc<Deck>; 2],
+    // What the decks are called in replays, a deck id or file works best
+    pub names: [String; 2],
+    pub kinds: [PlayerKind; 2],
+    pub games: u32,
+    pub seed: u64,
+    // Folder to write a replay of every game to
+    pub replays: Option<String>,
+}
+
+impl Series {
+    pub fn new(decks: [Arc<Deck>; 2], kinds: [PlayerKind; 2], games: u32, seed: u64) -> Self {
+        Series {
+            decks,
+            names: ["A".to_string(), "B".to_string()],
+            kinds,
+            games,
+            seed,
+            replays: None,
+        }
+    }
+}
+
+// One finished game of a series
+pub struct SeriesGame {
+    pub result: GameResult,
+    // The side that went first
+    pub first: usize,
+    pub timeline: Timeline,
+    pub events: Vec<LogEvent>,
+    // Houses of the cards each seat held going into the first turn
+    pub opening_hands: [Vec<House>; 2],
+}
+
+// Plays one game of a series. Sides take turns going first, A in even games.
+pub fn play_series_game(series: &Series, game: u32) -> Result<SeriesGame, String> {
+    let mut rng = StdRng::seed_from_u64(game_seed(series.seed, game));
+    let first = (game % 2) as usize;
+    let second = 1 - first;
+    let first_board = DeckBoard::seeded(&series.decks[first], rng.gen());
+    let second_board = DeckBoard::seeded(&series.decks[second], rng.gen());
+    let first_player = series.kinds[first].create(rng.gen())?;
+    let second_player = series.kinds[second].create(rng.gen())?;
+    let mut play = Game::new(first_board, second_board, first_player, second_player);
+    play.record([series.names[first].clone(), series.names[second].clone()]);
+    play.start();
+    let hand = |seat| play.board(seat).hand.iter().map(|card| card.house.clone()).collect();
+    let opening_hands = [hand(0), hand(1)];
+    let result = play.play();
+
+    if let (Some(dir), Some(events)) = (series.replays.as_ref(), play.events()) {
+        let path = Path::new(dir).join(format!("game-{}.jsonl", game));
+        let file = File::create(&path).map_err(|err| format!("Couldn't create {}: {}", path.display(), err))?;
+        replay::write_log(BufWriter::new(file), events)
+            .map_err(|err| format!("Couldn't write {}: {}", path.display(), err))?;
+    }
+    Ok(SeriesGame {
+        result,
+        first,
+        timeline: play.timeline,
+        events: play.log.unwrap_or_default(),
+        opening_hands,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Game, GameResult, MatchStats};
+    use timeline::TurnStats;
+    use api::test::deck_from;
+    use board::DeckBoard;
+    use game::test::test_card;
+    use game::{House, Type};
+    use player::greedy::GreedyPlayer;
+
+    #[test]
+    fn test_game_forges_to_win() {
+        let deck_a = deck_from(test_card(House::Brobnar, Type::Action, 2, 0, 0), 36);
+        let deck_b = deck_from(test_card(House::Dis, Type::Action, 1, 0, 0), 36);
+        let mut game = Game::new(
+            DeckBoard::new(&deck_a),
+            DeckBoard::new(&deck_b),
+            Box::new(GreedyPlayer),
+            Box::new(GreedyPlayer),
+        );
+
+        // Both players empty their hands every turn
+        game.play_turn();
+        assert_eq!(game.active(), 1);
+        assert_eq!(game.board(0).amber, 12);
+        assert_eq!(game.board(0).hand.len(), 6);
+        game.play_turn();
+        assert_eq!(game.board(1).amber, 6);
+
+        let result = game.play();
+        assert_eq!(result.winner, Some(0));
+        // Seat 0 forges at the start of turns 3, 5 and 7
+        assert_eq!(result.turns, 7);
+        assert_eq!(game.board(0).keys, 3);
+        assert_eq!(game.timeline.len(), 7);
+        assert_eq!(game.timeline[0], TurnStats { seat: 0, amber: 12, keys: 0, creatures: 0, cards_played: 6 });
+        assert_eq!(game.timeline[6], TurnStats { seat: 0, amber: 18, keys: 3, creatures: 0, cards_played: 0 });
+    }
+
+    #[test]
+    fn test_game_draw_after_max_turns() {
+        let deck = deck_from(test_card(House::Brobnar, Type::Action, 0, 0, 0), 36);
+        let mut game = Game::new(
+            DeckBoard::new(&deck),
+            DeckBoard::new(&deck),
+            Box::new(GreedyPlayer),
+            Box::new(GreedyPlayer),
+        );
+        game.max_turns = 10;
+        let result = game.play();
+        assert_eq!(result.winner, None);
+        assert_eq!(result.turns, 10);
+    }
+
+    #[test]
+    fn test_match_stats() {
+        let mut stats = MatchStats::default();
+        stats.record(&GameResult { winner: Some(0), turns: 7, mulligans: [false, false] }, 0);
+        stats.record(&GameResult { winner: Some(0), turns: 9, mulligans: [true, false] }, 1);
+        stats.record(&GameResult { winner: Some(1), turns: 8, mulligans: [true, false] }, 1);
+        stats.record(&GameResult { winner: None, turns: 200, mulligans: [false, false] }, 0);
+        assert_eq!(stats.wins, [2, 1]);
+        assert_eq!(stats.draws, 1);
+        assert_eq!(stats.win_rate(0), 0.5);
+        assert_eq!(stats.average_turns(), 56.0);
+        assert_eq!(stats.first_player_win_rate(), 2.0 / 3.0);
+        // Both mulligans were B's, who won once of those twice
+        assert_eq!(stats.mulligans, [0, 2]);
+        assert_eq!(stats.mulligan_win_rate(1), 0.5);
+
+        let mut total = stats.clone();
+        total.merge(&stats);
+        assert_eq!(total.games, 8);
+        assert_eq!(total.wins, [4, 2]);
+        assert_eq!(total.average_turns(), 56.0);
+        assert_eq!(total.mulligan_wins, [0, 2]);
+    }
+
+    #[test]
+    fn test_game_start() {
+        // Greedy sends back hands without three cards of a house
+        let deck_a = deck_from(test_card(House::Brobnar, Type::Action, 1, 0, 0), 36);
+        let mut deck_b = deck_from(test_card(House::Dis, Type::Action, 1, 0, 0), 12);
+        for house in [House::Logos, House::Mars] {
+            let card = test_card(house.clone(), Type::Action, 1, 0, 0);
+            deck_b.houses.push(house);
+            for _ in 0..12 {
+                deck_b.cards.push(card.id.clone());
+            }
+            deck_b.card_details.insert(card.id.clone(), card);
+        }
+        let mut second = DeckBoard::seeded(&deck_b, 1);
+        second.deck.append(&mut second.hand);
+        for house in &[House::Dis, House::Logos, House::Mars] {
+            for _ in 0..2 {
+                let index = second.deck.iter().position(|c| c.house == *house).unwrap();
+                let card = second.deck.remove(index);
+                second.hand.push(card);
+            }
+        }
+
+        let mut game = Game::new(DeckBoard::seeded(&deck_a, 2), second, Box::new(GreedyPlayer), Box::new(GreedyPlayer));
+        game.start();
+        assert_eq!(game.board(0).hand.len(), 7);
+        assert_eq!(game.board(1).hand.len(), 5);
+        assert_eq!(game.board(1).deck.len(), 31);
+        assert_eq!(game.play().mulligans, [false, true]);
+    }
+}
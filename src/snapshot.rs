@@ -0,0 +1,348 @@
+// Snapshots of a game in progress, for saving puzzles and resuming games.
+//
+// Boards hold references into the decks they were dealt from, so a snapshot
+// names cards by id instead and is loaded back against the same decks. The
+// shuffle rng can't be saved, so a loaded board is reseeded from `seed`: the
+// deck keeps its order, but later reshuffles of the discard come out
+// differently than they would have.
+
+use api::Deck;
+use board::{DeckBoard, Zone};
+use field::{Artifact, Creature, Owner, Upgrade};
+use game::{Card, House};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde_json;
+use std::io::{Read, Write};
+use trigger::Ability;
+
+// One copy of a card, by its id and its slot in the deck it was dealt from
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct CardSnapshot {
+    pub id: String,
+    pub slot: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UpgradeSnapshot {
+    #[serde(flatten)]
+    pub card: CardSnapshot,
+    // Snapshots saved before upgrades had their own owner leave it out
+    #[serde(default = "controller")]
+    pub owner: Owner,
+}
+
+fn controller() -> Owner {
+    Owner::Controller
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct CreatureSnapshot {
+    pub card: CardSnapshot,
+    pub owner: Owner,
+    pub on_flank: bool,
+    pub damage: u32,
+    pub armor: u32,
+    pub stunned: bool,
+    pub exhausted: bool,
+    pub warded: bool,
+    pub elusive: bool,
+    pub skirmish: bool,
+    pub attacked: bool,
+    // Left out of snapshots saved before they were kept
+    #[serde(default)]
+    pub fought: bool,
+    #[serde(default)]
+    pub damage_taken: u32,
+    pub captured: u32,
+    pub upgrades: Vec<UpgradeSnapshot>,
+    pub leaves_to: Zone,
+    pub abilities: Vec<Ability>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ArtifactSnapshot {
+    pub card: CardSnapshot,
+    pub exhausted: bool,
+    pub owner: Owner,
+    pub abilities: Vec<Ability>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct BoardSnapshot {
+    // The deck id or file the board was dealt from
+    pub name: String,
+    pub seed: u64,
+    pub hand: Vec<CardSnapshot>,
+    // The top card is last
+    pub deck: Vec<CardSnapshot>,
+    pub discard: Vec<CardSnapshot>,
+    pub archives: Vec<CardSnapshot>,
+    pub purged: Vec<CardSnapshot>,
+    pub creatures: Vec<CreatureSnapshot>,
+    pub artifacts: Vec<ArtifactSnapshot>,
+    pub amber: u32,
+    pub keys: u32,
+    pub chains: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct GameSnapshot {
+    // By seat, 0 for whoever went first
+    pub boards: [BoardSnapshot; 2],
+    // Seat of the player whose turn it is
+    pub active: usize,
+    pub turns: u32,
+    // The house being played if the turn is under way, None if it is yet
+    // to start
+    pub house: Option<House>,
+}
+
+impl CardSnapshot {
+    pub fn new(card: &Card) -> Self {
+        CardSnapshot { id: card.id.clone(), slot: card.slot }
+    }
+}
+
+fn snapshots(cards: &[Card]) -> Vec<CardSnapshot> {
+    cards.iter().map(CardSnapshot::new).collect()
+}
+
+// Looks a card up in the board's own deck first. Cards taken from the
+// opponent are found in theirs.
+fn find_card<'a>(decks: [&'a Deck; 2], card: &CardSnapshot) -> Result<Card<'a>, String> {
+    decks.iter()
+        .find(|deck| deck.cards.get(card.slot) == Some(&card.id))
+        .map(|deck| deck.card(card.slot))
+        .ok_or_else(|| format!("Card {} isn't in slot {} of either deck", card.id, card.slot))
+}
+
+fn find_cards<'a>(decks: [&'a Deck; 2], cards: &[CardSnapshot]) -> Result<Vec<Card<'a>>, String> {
+    cards.iter().map(|card| find_card(decks, card)).collect()
+}
+
+impl UpgradeSnapshot {
+    pub fn new(upgrade: &Upgrade) -> Self {
+        UpgradeSnapshot { card: CardSnapshot::new(&upgrade.card), owner: upgrade.owner }
+    }
+
+    fn load<'a>(&self, decks: [&'a Deck; 2]) -> Result<Upgrade<'a>, String> {
+        Ok(Upgrade { card: find_card(decks, &self.card)?, owner: self.owner })
+    }
+}
+
+impl CreatureSnapshot {
+    pub fn new(creature: &Creature) -> Self {
+        CreatureSnapshot {
+            card: CardSnapshot::new(&creature.details),
+            owner: creature.owner,
+            on_flank: creature.on_flank,
+            damage: creature.damage,
+            armor: creature.armor,
+            stunned: creature.stunned,
+            exhausted: creature.exhausted,
+            warded: creature.warded,
+            elusive: creature.elusive,
+            skirmish: creature.skirmish,
+            attacked: creature.attacked,
+            fought: creature.fought,
+            damage_taken: creature.damage_taken,
+            captured: creature.captured,
+            upgrades: creature.upgrades.iter().map(UpgradeSnapshot::new).collect(),
+            leaves_to: creature.leaves_to,
+            abilities: creature.abilities.clone(),
+        }
+    }
+
+    fn load<'a>(&self, decks: [&'a Deck; 2]) -> Result<Creature<'a>, String> {
+        Ok(Creature {
+            details: find_card(decks, &self.card)?,
+            owner: self.owner,
+            on_flank: self.on_flank,
+            damage: self.damage,
+            armor: self.armor,
+            stunned: self.stunned,
+            exhausted: self.exhausted,
+            warded: self.warded,
+            elusive: self.elusive,
+            skirmish: self.skirmish,
+            attacked: self.attacked,
+            fought: self.fought,
+            damage_taken: self.damage_taken,
+            destroyed: false,
+            captured: self.captured,
+            upgrades: self.upgrades.iter().map(|u| u.load(decks)).collect::<Result<_, _>>()?,
+            leaves_to: self.leaves_to,
+            abilities: self.abilities.clone(),
+        })
+    }
+}
+
+impl ArtifactSnapshot {
+    pub fn new(artifact: &Artifact) -> Self {
+        ArtifactSnapshot {
+            card: CardSnapshot::new(&artifact.details),
+            exhausted: artifact.exhausted,
+            owner: artifact.owner,
+            abilities: artifact.abilities.clone(),
+        }
+    }
+
+    fn load<'a>(&self, decks: [&'a Deck; 2]) -> Result<Artifact<'a>, String> {
+        Ok(Artifact {
+            details: find_card(decks, &self.card)?,
+            exhausted: self.exhausted,
+            owner: self.owner,
+            abilities: self.abilities.clone(),
+        })
+    }
+}
+
+impl BoardSnapshot {
+    pub fn new(name: &str, board: &DeckBoard) -> Self {
+        BoardSnapshot {
+            name: name.to_string(),
+            seed: board.seed,
+            hand: snapshots(&board.hand),
+            deck: snapshots(&board.deck),
+            discard: snapshots(&board.discard),
+            archives: snapshots(&board.archives),
+            purged: snapshots(&board.purged),
+            creatures: board.creatures.iter().map(CreatureSnapshot::new).collect(),
+            artifacts: board.artifacts.iter().map(ArtifactSnapshot::new).collect(),
+            amber: board.amber,
+            keys: board.keys,
+            chains: board.chains,
+        }
+    }
+
+    // `deck` is the one this board was dealt from, `other` the opponent's
+    pub fn load<'a>(&self, deck: &'a Deck, other: &'a Deck) -> Result<DeckBoard<'a>, String> {
+        let decks = [deck, other];
+        Ok(DeckBoard {
+            deck_details: deck,
+            hand: find_cards(decks, &self.hand)?,
+            deck: find_cards(decks, &self.deck)?,
+            discard: find_cards(decks, &self.discard)?,
+            archives: find_cards(decks, &self.archives)?,
+            purged: find_cards(decks, &self.purged)?,
+            creatures: self.creatures.iter().map(|c| c.load(decks)).collect::<Result<_, _>>()?,
+            artifacts: self.artifacts.iter().map(|a| a.load(decks)).collect::<Result<_, _>>()?,
+            amber: self.amber,
+            keys: self.keys,
+            chains: self.chains,
+            rng: StdRng::seed_from_u64(self.seed),
+            seed: self.seed,
+        })
+    }
+}
+
+impl GameSnapshot {
+    // `names` are what each seat's deck is called, `boards` are by seat
+    pub fn new(names: &[String; 2], boards: [&DeckBoard; 2], active: usize, turns: u32, house: Option<House>)
+               -> Self {
+        GameSnapshot {
+            boards: [BoardSnapshot::new(&names[0], boards[0]), BoardSnapshot::new(&names[1], boards[1])],
+            active,
+            turns,
+            house,
+        }
+    }
+
+    pub fn names(&self) -> [String; 2] {
+        [self.boards[0].name.clone(), self.boards[1].name.clone()]
+    }
+
+    // Rebuilds both boards, by seat, from the decks they were dealt from
+    pub fn load<'a>(&self, decks: [&'a Deck; 2]) -> Result<(DeckBoard<'a>, DeckBoard<'a>), String> {
+        if self.active > 1 {
+            return Err(format!("There is no seat {}", self.active));
+        }
+        if let Some(ref house) = self.house {
+            if !decks[self.active].houses.contains(house) {
+                return Err(format!("Seat {} can't play {}", self.active, house));
+            }
+        }
+        let first = self.boards[0].load(decks[0], decks[1])?;
+        let second = self.boards[1].load(decks[1], decks[0])?;
+        Ok((first, second))
+    }
+
+    pub fn read<R: Read>(input: R) -> Result<Self, String> {
+        serde_json::from_reader(input).map_err(|err| format!("Bad snapshot: {}", err))
+    }
+
+    pub fn write<W: Write>(&self, out: W) -> Result<(), String> {
+        serde_json::to_writer_pretty(out, self).map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GameSnapshot;
+    use api::test::deck_from;
+    use board::DeckBoard;
+    use game::test::test_card;
+    use game::{House, Type};
+    use player::greedy::GreedyPlayer;
+    use player::RandomPlayer;
+    use simulation::Game;
+    use std::io::Cursor;
+    use trigger::{Ability, Effect, When};
+
+    fn names() -> [String; 2] {
+        ["a".to_string(), "b".to_string()]
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let deck_a = deck_from(test_card(House::Brobnar, Type::Creature, 1, 3, 1), 36);
+        let deck_b = deck_from(test_card(House::Dis, Type::Creature, 1, 2, 0), 36);
+        let mut game = Game::new(DeckBoard::seeded(&deck_a, 1), DeckBoard::seeded(&deck_b, 2),
+                                 Box::new(GreedyPlayer), Box::new(RandomPlayer::seeded(4)));
+        for _ in 0..6 {
+            game.play_turn();
+        }
+        let mut snapshot = game.snapshot(&names());
+        assert_eq!(snapshot.turns, 6);
+        assert_eq!(snapshot.house, None);
+        assert!(!snapshot.boards[0].creatures.is_empty());
+        snapshot.boards[0].creatures[0].warded = true;
+        snapshot.boards[0].creatures[0].abilities.push(Ability::new(When::Reap, Effect::GainAmber(1)));
+
+        let mut json = Vec::new();
+        snapshot.write(&mut json).unwrap();
+        let loaded = GameSnapshot::read(Cursor::new(json)).unwrap();
+        assert_eq!(loaded, snapshot);
+
+        let mut resumed = Game::resume(&loaded, [&deck_a, &deck_b],
+                                       Box::new(GreedyPlayer), Box::new(GreedyPlayer)).unwrap();
+        assert_eq!(resumed.snapshot(&names()), snapshot);
+        assert!(resumed.board(0).creatures[0].warded);
+        resumed.play();
+
+        // Cards must come from the decks being played
+        let deck_c = deck_from(test_card(House::Logos, Type::Creature, 1, 2, 0), 36);
+        assert!(loaded.load([&deck_c, &deck_b]).is_err());
+    }
+
+    #[test]
+    fn test_resume_mid_turn() {
+        let deck = deck_from(test_card(House::Brobnar, Type::Action, 1, 0, 0), 36);
+        let mut snapshot = GameSnapshot::new(&names(), [&DeckBoard::seeded(&deck, 1), &DeckBoard::seeded(&deck, 2)],
+                                             1, 3, Some(House::Brobnar));
+        snapshot.boards[1].amber = 7;
+
+        // The key step is already over, so seat 1 just plays out the turn
+        let mut game = Game::resume(&snapshot, [&deck, &deck], Box::new(GreedyPlayer), Box::new(GreedyPlayer))
+            .unwrap();
+        assert_eq!(game.play_turn(), None);
+        assert_eq!(game.board(1).keys, 0);
+        assert_eq!(game.board(1).amber, 13);
+        assert_eq!(game.turns(), 4);
+        assert_eq!(game.active(), 0);
+
+        snapshot.house = Some(House::Dis);
+        assert!(Game::resume(&snapshot, [&deck, &deck], Box::new(GreedyPlayer), Box::new(GreedyPlayer)).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+// How amber, keys and board presence build up over a game, for judging a
+// deck's tempo.
+
+use board::{DeckBoard, KEYS_TO_WIN};
+use std::io::{self, Write};
+
+// One player's position at the end of one of their turns
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct TurnStats {
+    pub seat: usize,
+    pub amber: u32,
+    pub keys: u32,
+    pub creatures: usize,
+    pub cards_played: u32,
+}
+
+impl TurnStats {
+    pub fn new(seat: usize, board: &DeckBoard, cards_played: u32) -> Self {
+        TurnStats {
+            seat,
+            amber: board.amber,
+            keys: board.keys,
+            creatures: board.creatures.len(),
+            cards_played,
+        }
+    }
+}
+
+// Every turn of one game, in order
+pub type Timeline = Vec<TurnStats>;
+
+// Running totals over every game that reached a side's nth turn
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct TurnTotals {
+    pub games: u32,
+    pub amber: u64,
+    pub keys: u64,
+    pub creatures: u64,
+    pub cards_played: u64,
+}
+
+impl TurnTotals {
+    fn add(&mut self, turn: &TurnStats) {
+        self.games += 1;
+        self.amber += u64::from(turn.amber);
+        self.keys += u64::from(turn.keys);
+        self.creatures += turn.creatures as u64;
+        self.cards_played += u64::from(turn.cards_played);
+    }
+
+    fn merge(&mut self, other: &TurnTotals) {
+        self.games += other.games;
+        self.amber += other.amber;
+        self.keys += other.keys;
+        self.creatures += other.creatures;
+        self.cards_played += other.cards_played;
+    }
+
+    fn mean(&self, total: u64) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            total as f64 / f64::from(self.games)
+        }
+    }
+
+    pub fn mean_amber(&self) -> f64 {
+        self.mean(self.amber)
+    }
+
+    pub fn mean_keys(&self) -> f64 {
+        self.mean(self.keys)
+    }
+
+    pub fn mean_creatures(&self) -> f64 {
+        self.mean(self.creatures)
+    }
+
+    pub fn mean_cards_played(&self) -> f64 {
+        self.mean(self.cards_played)
+    }
+}
+
+// Timelines of a series, by side A (0) and B (1). Turns are counted per
+// side from 0, so a side's curves line up whether or not it went first.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct TimelineStats {
+    pub turns: [Vec<TurnTotals>; 2],
+    // For each side and key, how many games it was forged by the end of
+    // each of the side's turns
+    pub key_turns: [[Vec<u32>; KEYS_TO_WIN as usize]; 2],
+}
+
+fn count_at<T: Default>(counts: &mut Vec<T>, index: usize) -> &mut T {
+    if counts.len() <= index {
+        counts.resize_with(index + 1, T::default);
+    }
+    &mut counts[index]
+}
+
+impl TimelineStats {
+    // `first` is the side that took the first turn
+    pub fn record(&mut self, timeline: &[TurnStats], first: usize) {
+        let mut own_turns = [0, 0];
+        let mut keys = [0, 0];
+        for turn in timeline {
+            let side = if turn.seat == 0 { first } else { 1 - first };
+            let index = own_turns[side];
+            own_turns[side] += 1;
+            count_at(&mut self.turns[side], index).add(turn);
+            while keys[side] < turn.keys.min(KEYS_TO_WIN) {
+                *count_at(&mut self.key_turns[side][keys[side] as usize], index) += 1;
+                keys[side] += 1;
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &TimelineStats) {
+        for side in 0..2 {
+            for (index, totals) in other.turns[side].iter().enumerate() {
+                count_at(&mut self.turns[side], index).merge(totals);
+            }
+            for key in 0..KEYS_TO_WIN as usize {
+                for (index, &count) in other.key_turns[side][key].iter().enumerate() {
+                    *count_at(&mut self.key_turns[side][key], index) += count;
+                }
+            }
+        }
+    }
+
+    // One row per side and turn, turns numbered from 1. The key columns
+    // count the games in which that key was forged on that turn.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "side,turn,games,mean_amber,mean_keys,mean_creatures,mean_cards_played,\
+                       first_key,second_key,third_key")?;
+        for side in 0..2 {
+            for (index, totals) in self.turns[side].iter().enumerate() {
+                write!(out, "{},{},{},{:.3},{:.3},{:.3},{:.3}",
+                       ["A", "B"][side], index + 1, totals.games, totals.mean_amber(), totals.mean_keys(),
+                       totals.mean_creatures(), totals.mean_cards_played())?;
+                for key in 0..KEYS_TO_WIN as usize {
+                    write!(out, ",{}", self.key_turns[side][key].get(index).cloned().unwrap_or(0))?;
+                }
+                writeln!(out)?;
+            }
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TimelineStats, TurnStats};
+
+    fn turn(seat: usize, amber: u32, keys: u32) -> TurnStats {
+        TurnStats { seat, amber, keys, creatures: 1, cards_played: 2 }
+    }
+
+    #[test]
+    fn test_timeline_stats() {
+        let mut stats = TimelineStats::default();
+        // B went first, forged on its second turn and won on its third
+        stats.record(&[turn(0, 6, 0), turn(1, 2, 0), turn(0, 1, 1), turn(1, 4, 0), turn(0, 0, 3)], 1);
+        assert_eq!(stats.turns[1].len(), 3);
+        assert_eq!(stats.turns[0].len(), 2);
+        assert_eq!(stats.turns[0][1].mean_amber(), 4.0);
+        assert_eq!(stats.key_turns[1][0], vec![0, 1]);
+        assert_eq!(stats.key_turns[1][2], vec![0, 0, 1]);
+        assert!(stats.key_turns[0][0].is_empty());
+
+        let mut total = stats.clone();
+        total.merge(&stats);
+        assert_eq!(total.turns[1][0].games, 2);
+        assert_eq!(total.turns[1][0].mean_amber(), 6.0);
+        assert_eq!(total.key_turns[1][1], vec![0, 0, 2]);
+
+        let mut csv = Vec::new();
+        total.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[1], "A,1,2,2.000,0.000,1.000,2.000,0,0,0");
+        assert_eq!(lines[5], "B,3,2,0.000,3.000,1.000,2.000,0,2,2");
+    }
+}
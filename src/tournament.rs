@@ -0,0 +1,349 @@
+use api::{self, Deck};
+use player::PlayerKind;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use runner;
+use simulation::{game_seed, Series};
+use std::fmt::{self, Write};
+use std::fs;
+use std::sync::Arc;
+
+// Elo of a deck with average strength
+pub const BASE_ELO: f64 = 1500.0;
+// Resamples used for the confidence intervals
+pub const BOOTSTRAP_SAMPLES: u32 = 1000;
+
+const MAX_ITERATIONS: u32 = 10_000;
+const TOLERANCE: f64 = 1e-10;
+
+// Every saved deck in a folder, sorted by file name and named after it
+pub fn load_decks(dir: &str) -> Result<Vec<(String, Deck)>, String> {
+    let entries = fs::read_dir(dir).map_err(|err| format!("Couldn't read {}: {}", dir, err))?;
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "deck"))
+        .collect();
+    paths.sort();
+
+    let mut decks = Vec::new();
+    for path in paths {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let path = path.to_string_lossy();
+        let deck = api::load_deck_file(&path).map_err(|err| format!("{}: {}", path, err))?;
+        decks.push((name, deck));
+    }
+    Ok(decks)
+}
+
+// Head to head results, indexed [deck][opponent]
+#[derive(Debug, PartialEq, Clone)]
+pub struct Results {
+    pub wins: Vec<Vec<u32>>,
+    pub draws: Vec<Vec<u32>>,
+}
+
+impl Results {
+    pub fn new(decks: usize) -> Self {
+        Results {
+            wins: vec![vec![0; decks]; decks],
+            draws: vec![vec![0; decks]; decks],
+        }
+    }
+
+    pub fn decks(&self) -> usize {
+        self.wins.len()
+    }
+
+    pub fn games(&self, deck: usize, opponent: usize) -> u32 {
+        self.wins[deck][opponent] + self.wins[opponent][deck] + self.draws[deck][opponent]
+    }
+
+    // Wins, draws and losses over the whole tournament
+    pub fn record(&self, deck: usize) -> (u32, u32, u32) {
+        let wins = self.wins[deck].iter().sum();
+        let draws = self.draws[deck].iter().sum();
+        let losses = self.wins.iter().map(|row| row[deck]).sum();
+        (wins, draws, losses)
+    }
+}
+
+// Plays every pair of decks `games` times in each seat order
+pub fn play_round_robin(decks: &[Arc<Deck>], kind: &PlayerKind, games: u32, seed: u64, threads: usize)
+                        -> Result<Results, String> {
+    let mut results = Results::new(decks.len());
+    let mut pair = 0;
+    for a in 0..decks.len() {
+        for b in a + 1..decks.len() {
+            let kinds = [kind.clone(), kind.clone()];
+            let series = Series::new([decks[a].clone(), decks[b].clone()], kinds,
+                                     2 * games, game_seed(seed, pair));
+            let stats = runner::run_series(&series, threads)?;
+            results.wins[a][b] = stats.wins[0];
+            results.wins[b][a] = stats.wins[1];
+            results.draws[a][b] = stats.draws;
+            results.draws[b][a] = stats.draws;
+            pair += 1;
+        }
+    }
+    Ok(results)
+}
+
+// Bradley-Terry strengths fitted with Hunter's MM algorithm, scaled so their
+// geometric mean is 1. A draw counts as half a win for each side, and every
+// pair that played starts with one extra virtual draw so that undefeated and
+// winless decks still get finite strengths.
+pub fn bradley_terry(results: &Results) -> Vec<f64> {
+    let n = results.decks();
+    let score = |i: usize, j: usize| {
+        f64::from(results.wins[i][j]) + 0.5 * f64::from(results.draws[i][j]) + 0.5
+    };
+    let games = |i: usize, j: usize| f64::from(results.games(i, j)) + 1.0;
+    let played = |i: usize, j: usize| i != j && results.games(i, j) > 0;
+
+    let mut strengths = vec![1.0; n];
+    for _ in 0..MAX_ITERATIONS {
+        let mut next = strengths.clone();
+        for i in 0..n {
+            let mut won = 0.0;
+            let mut expected = 0.0;
+            for j in (0..n).filter(|&j| played(i, j)) {
+                won += score(i, j);
+                expected += games(i, j) / (strengths[i] + strengths[j]);
+            }
+            if expected > 0.0 {
+                next[i] = won / expected;
+            }
+        }
+        let log_mean = next.iter().map(|s| s.ln()).sum::<f64>() / n as f64;
+        for strength in &mut next {
+            *strength /= log_mean.exp();
+        }
+        let change = next.iter().zip(&strengths).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+        strengths = next;
+        if change < TOLERANCE {
+            break;
+        }
+    }
+    strengths
+}
+
+pub fn elo(strength: f64) -> f64 {
+    BASE_ELO + 400.0 * strength.log10()
+}
+
+// Replays the tournament from the observed outcome rates of every pair
+fn resample<R: Rng>(results: &Results, rng: &mut R) -> Results {
+    let n = results.decks();
+    let mut sample = Results::new(n);
+    for a in 0..n {
+        for b in a + 1..n {
+            let games = results.games(a, b);
+            if games == 0 {
+                continue;
+            }
+            let a_wins = results.wins[a][b];
+            let b_wins = results.wins[b][a];
+            for _ in 0..games {
+                let outcome = rng.gen_range(0, games);
+                if outcome < a_wins {
+                    sample.wins[a][b] += 1;
+                } else if outcome < a_wins + b_wins {
+                    sample.wins[b][a] += 1;
+                } else {
+                    sample.draws[a][b] += 1;
+                    sample.draws[b][a] += 1;
+                }
+            }
+        }
+    }
+    sample
+}
+
+// The 2.5th and 97.5th percentile Elo of every deck over bootstrap resamples
+pub fn confidence_intervals(results: &Results, samples: u32, seed: u64) -> Vec<(f64, f64)> {
+    let n = results.decks();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut ratings: Vec<Vec<f64>> = vec![Vec::new(); n];
+    for _ in 0..samples {
+        let strengths = bradley_terry(&resample(results, &mut rng));
+        for (deck, strength) in strengths.into_iter().enumerate() {
+            ratings[deck].push(elo(strength));
+        }
+    }
+    ratings.into_iter().map(|mut elos| {
+        if elos.is_empty() {
+            return (BASE_ELO, BASE_ELO);
+        }
+        elos.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let at = |fraction: f64| elos[((elos.len() - 1) as f64 * fraction).round() as usize];
+        (at(0.025), at(0.975))
+    }).collect()
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Rating {
+    pub deck: String,
+    pub elo: f64,
+    pub elo_low: f64,
+    pub elo_high: f64,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Report {
+    pub decks: Vec<String>,
+    pub player: String,
+    // Games per pair in each seat order
+    pub games: u32,
+    pub seed: u64,
+    // Indexed [deck][opponent] in the order of `decks`
+    pub wins: Vec<Vec<u32>>,
+    pub draws: Vec<Vec<u32>>,
+    // Best first
+    pub ratings: Vec<Rating>,
+}
+
+impl Report {
+    pub fn new(names: Vec<String>, player: &PlayerKind, games: u32, seed: u64, results: Results) -> Self {
+        let strengths = bradley_terry(&results);
+        let intervals = confidence_intervals(&results, BOOTSTRAP_SAMPLES, seed);
+        let mut ratings: Vec<Rating> = names.iter().enumerate().map(|(deck, name)| {
+            let (wins, draws, losses) = results.record(deck);
+            Rating {
+                deck: name.clone(),
+                elo: elo(strengths[deck]),
+                elo_low: intervals[deck].0,
+                elo_high: intervals[deck].1,
+                wins,
+                draws,
+                losses,
+            }
+        }).collect();
+        ratings.sort_by(|a, b| b.elo.partial_cmp(&a.elo).unwrap());
+        Report {
+            decks: names,
+            player: format!("{:?}", player),
+            games,
+            seed,
+            wins: results.wins,
+            draws: results.draws,
+            ratings,
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.decks.iter().map(|d| d.len()).max().unwrap_or(0).max(4);
+        writeln!(f, "{:>4}  {:<width$}  {:>6}  {:>15}  {:>11}", "Rank", "Deck", "Elo", "95% CI", "W-D-L", width = width)?;
+        for (rank, rating) in self.ratings.iter().enumerate() {
+            let interval = format!("{:.0} - {:.0}", rating.elo_low, rating.elo_high);
+            let record = format!("{}-{}-{}", rating.wins, rating.draws, rating.losses);
+            writeln!(f, "{:>4}  {:<width$}  {:>6.0}  {:>15}  {:>11}",
+                     rank + 1, rating.deck, rating.elo, interval, record, width = width)?;
+        }
+
+        // Wins of the row deck against the column deck
+        writeln!(f)?;
+        // Rows are labelled with their rank as well as the deck
+        let width = width + self.decks.len().to_string().len() + 1;
+        let mut header = format!("{:<width$}", "Wins", width = width);
+        for column in 0..self.decks.len() {
+            write!(header, "  {:>5}", column + 1).unwrap();
+        }
+        writeln!(f, "{}", header)?;
+        for (row, name) in self.decks.iter().enumerate() {
+            let mut line = format!("{:<width$}", format!("{} {}", row + 1, name), width = width);
+            for column in 0..self.decks.len() {
+                if row == column {
+                    write!(line, "  {:>5}", "-").unwrap();
+                } else {
+                    write!(line, "  {:>5}", self.wins[row][column]).unwrap();
+                }
+            }
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bradley_terry, confidence_intervals, elo, play_round_robin, Report, Results, BASE_ELO};
+    use api::test::deck_from;
+    use game::test::test_card;
+    use game::{House, Type};
+    use player::PlayerKind;
+    use std::sync::Arc;
+
+    fn results(wins: Vec<Vec<u32>>) -> Results {
+        let n = wins.len();
+        Results { wins, draws: vec![vec![0; n]; n] }
+    }
+
+    #[test]
+    fn test_even_decks_rate_equally() {
+        let even = results(vec![vec![0, 10, 10], vec![10, 0, 10], vec![10, 10, 0]]);
+        for strength in bradley_terry(&even) {
+            assert!((elo(strength) - BASE_ELO).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_stronger_deck_rates_higher() {
+        let lopsided = results(vec![vec![0, 15, 18], vec![5, 0, 12], vec![2, 8, 0]]);
+        let strengths = bradley_terry(&lopsided);
+        assert!(strengths[0] > strengths[1] && strengths[1] > strengths[2]);
+
+        // 3:1 odds, counting the virtual draw, are about 191 Elo
+        let pair = results(vec![vec![0, 58], vec![19, 0]]);
+        let strengths = bradley_terry(&pair);
+        assert!((elo(strengths[0]) - elo(strengths[1]) - 191.0).abs() < 1.0);
+
+        let intervals = confidence_intervals(&lopsided, 200, 1);
+        let elos: Vec<f64> = bradley_terry(&lopsided).into_iter().map(elo).collect();
+        for (elo, &(low, high)) in elos.iter().zip(&intervals) {
+            assert!(low <= *elo && *elo <= high);
+        }
+        assert!(intervals[0].0 > intervals[2].1);
+    }
+
+    #[test]
+    fn test_undefeated_deck_is_finite() {
+        let strengths = bradley_terry(&results(vec![vec![0, 10], vec![0, 0]]));
+        assert!(strengths.iter().all(|s| s.is_finite() && *s > 0.0));
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let decks: Vec<_> = [(House::Brobnar, 3), (House::Dis, 2), (House::Logos, 1)].iter()
+            .map(|&(ref house, amber)| Arc::new(deck_from(test_card(house.clone(), Type::Action, amber, 0, 0), 36)))
+            .collect();
+        let results = play_round_robin(&decks, &PlayerKind::Greedy, 2, 9, 2).unwrap();
+        for a in 0..3 {
+            for b in 0..3 {
+                if a != b {
+                    assert_eq!(results.games(a, b), 4);
+                }
+            }
+        }
+        for deck in 0..3 {
+            let (wins, draws, losses) = results.record(deck);
+            assert_eq!(wins + draws + losses, 8);
+        }
+
+        let names = vec!["three".to_string(), "two".to_string(), "one".to_string()];
+        let report = Report::new(names, &PlayerKind::Greedy, 2, 9, results.clone());
+        assert_eq!(report.wins, results.wins);
+        assert_eq!(report.ratings.len(), 3);
+        assert!(report.ratings[0].elo >= report.ratings[2].elo);
+        let text = report.to_string();
+        assert!(text.contains("Rank  Deck"));
+        // The columns of the win matrix line up
+        let matrix: Vec<&str> = text.lines().skip_while(|line| !line.starts_with("Wins")).collect();
+        assert_eq!(matrix.len(), 4);
+        assert!(matrix.iter().all(|line| line.len() == matrix[0].len()));
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+use board::Zone;
+
+// Which side of the table something is on, relative to the active player
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Side {
+    Mine,
+    Opponent,
+}
+
+impl Side {
+    pub fn other(self) -> Side {
+        match self {
+            Side::Mine => Side::Opponent,
+            Side::Opponent => Side::Mine,
+        }
+    }
+}
+
+// The field card an ability belongs to, by index into its board
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Source {
+    Creature(usize),
+    Artifact(usize),
+}
+
+// Things that happen during a turn which abilities can respond to
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Event {
+    Played(Side, Source),
+    StartOfTurn(Side),
+    Reaped(Side, usize),
+    BeforeFight(Side, usize),
+    Fought(Side, usize),
+    Destroyed(Side, usize),
+}
+
+// When an ability goes off, from the point of view of the card that has it
+#[derive(Deserialize, Serialize, Debug, PartialEq, Copy, Clone)]
+pub enum When {
+    // "Play:"
+    Play,
+    // "At the start of your turn"
+    StartOfTurn,
+    // "Reap:"
+    Reap,
+    // "Before Fight:"
+    BeforeFight,
+    // "Fight:"
+    Fight,
+    // "Destroyed:"
+    Destroyed,
+    // "After a friendly creature reaps"
+    AfterFriendlyReap,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Copy, Clone)]
+pub enum Effect {
+    GainAmber(u32),
+    StealAmber(u32),
+    DrawCards(u32),
+    // Moves amber from the opponent's pool onto the source creature
+    Capture(u32),
+    // Changes where the source creature goes when it leaves play
+    LeavePlayTo(Zone),
+    // Moves an enemy creature to the right flank of the source's side
+    TakeCreature,
+    // Moves an enemy artifact to the source's side
+    TakeArtifact,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Copy, Clone)]
+pub struct Ability {
+    pub when: When,
+    pub effect: Effect,
+}
+
+impl Ability {
+    // Abilities only come from cards, but tests hand them out directly
+    #[cfg(test)]
+    pub fn new(when: When, effect: Effect) -> Self {
+        Ability { when, effect }
+    }
+
+    // Whether this ability, on a card at (side, source), goes off for the event
+    pub fn responds_to(&self, event: Event, side: Side, source: Source) -> bool {
+        let is_self = |event_side: Side, index: usize| {
+            event_side == side && source == Source::Creature(index)
+        };
+        match (self.when, event) {
+            (When::Play, Event::Played(s, played)) => s == side && played == source,
+            (When::StartOfTurn, Event::StartOfTurn(s)) => s == side,
+            (When::Destroyed, Event::Destroyed(s, i)) => is_self(s, i),
+            (When::Reap, Event::Reaped(s, i)) => is_self(s, i),
+            (When::BeforeFight, Event::BeforeFight(s, i)) => is_self(s, i),
+            (When::Fight, Event::Fought(s, i)) => is_self(s, i),
+            (When::AfterFriendlyReap, Event::Reaped(s, _)) => s == side,
+            _ => false,
+        }
+    }
+}
+
+// An ability that has gone off and is waiting to resolve
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Trigger {
+    pub side: Side,
+    pub source: Source,
+    pub effect: Effect,
+}
+
+pub struct TriggerQueue {
+    pending: VecDeque<Trigger>,
+}
+
+impl TriggerQueue {
+    pub fn new() -> Self {
+        TriggerQueue {
+            pending: VecDeque::new(),
+        }
+    }
+
+    // Queues a batch of simultaneous triggers in the given order.
+    // The order is expected to be a permutation of the batch indices; anything
+    // else falls back to the order the triggers were collected in.
+    pub fn push_ordered(&mut self, batch: Vec<Trigger>, order: &[usize]) {
+        let mut seen = vec![false; batch.len()];
+        let valid = order.len() == batch.len() && order.iter().all(|&i| {
+            i < seen.len() && !std::mem::replace(&mut seen[i], true)
+        });
+        if valid {
+            for &i in order {
+                self.pending.push_back(batch[i]);
+            }
+        } else {
+            warn!("Invalid trigger order {:?}, resolving in collected order", order);
+            self.pending.extend(batch);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Trigger> {
+        self.pending.pop_front()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Ability, Effect, Event, Side, Source, Trigger, TriggerQueue, When};
+
+    fn trigger(index: usize) -> Trigger {
+        Trigger {
+            side: Side::Mine,
+            source: Source::Creature(index),
+            effect: Effect::GainAmber(1),
+        }
+    }
+
+    #[test]
+    fn test_responds_to() {
+        let reap = Ability::new(When::Reap, Effect::GainAmber(1));
+        assert!(reap.responds_to(Event::Reaped(Side::Mine, 1), Side::Mine, Source::Creature(1)));
+        assert!(!reap.responds_to(Event::Reaped(Side::Mine, 0), Side::Mine, Source::Creature(1)));
+        assert!(!reap.responds_to(Event::Reaped(Side::Opponent, 1), Side::Mine, Source::Creature(1)));
+        assert!(!reap.responds_to(Event::Fought(Side::Mine, 1), Side::Mine, Source::Creature(1)));
+
+        let after_reap = Ability::new(When::AfterFriendlyReap, Effect::GainAmber(1));
+        assert!(after_reap.responds_to(Event::Reaped(Side::Mine, 3), Side::Mine, Source::Artifact(0)));
+        assert!(!after_reap.responds_to(Event::Reaped(Side::Opponent, 3), Side::Mine, Source::Artifact(0)));
+
+        let play = Ability::new(When::Play, Effect::GainAmber(1));
+        assert!(play.responds_to(Event::Played(Side::Mine, Source::Artifact(2)), Side::Mine, Source::Artifact(2)));
+        assert!(!play.responds_to(Event::Played(Side::Mine, Source::Creature(2)), Side::Mine, Source::Artifact(2)));
+
+        let start = Ability::new(When::StartOfTurn, Effect::GainAmber(1));
+        assert!(start.responds_to(Event::StartOfTurn(Side::Mine), Side::Mine, Source::Creature(0)));
+        assert!(!start.responds_to(Event::StartOfTurn(Side::Opponent), Side::Mine, Source::Creature(0)));
+    }
+
+    #[test]
+    fn test_queue_order() {
+        let mut queue = TriggerQueue::new();
+        queue.push_ordered(vec![trigger(0), trigger(1), trigger(2)], &[2, 0, 1]);
+        assert_eq!(queue.pop(), Some(trigger(2)));
+        assert_eq!(queue.pop(), Some(trigger(0)));
+        assert_eq!(queue.pop(), Some(trigger(1)));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_queue_invalid_order() {
+        let mut queue = TriggerQueue::new();
+        queue.push_ordered(vec![trigger(0), trigger(1)], &[1, 1]);
+        assert_eq!(queue.pop(), Some(trigger(0)));
+        assert_eq!(queue.pop(), Some(trigger(1)));
+        assert_eq!(queue.pop(), None);
+    }
+}
@@ -1,69 +1,637 @@
-use game::{House, CardDetails, Flank};
-use field::Creature;
-use board::DeckBoard;
+use game::{BonusIcon, Card, House, CardDetails, Flank, Type};
+use field::{FieldCard, Owner};
+use board::{DeckBoard, Zone};
 use player::Player;
+use replay::LogEvent;
+use trigger::{Effect, Event, Side, Source, Trigger, TriggerQueue};
+use view::PlayerView;
+use std::cmp::{min, Reverse};
+use std::mem;
 
 pub struct Turn<'a> {
     mine: DeckBoard<'a>,
     opponent: DeckBoard<'a>,
     house: House,
     player: Box<dyn Player>,
+    triggers: TriggerQueue,
+    // Cards played this turn
+    played: u32,
+    // The active player's seat and what happened so far, when recording
+    log: Option<(usize, Vec<LogEvent>)>,
+    // States before each applied action, and after each undone one
+    undo: Vec<Checkpoint<'a>>,
+    redo: Vec<Checkpoint<'a>>,
 }
 
+// Everything an action can change, so it can be put back exactly
+struct Checkpoint<'a> {
+    mine: DeckBoard<'a>,
+    opponent: DeckBoard<'a>,
+    played: u32,
+    log: Option<(usize, Vec<LogEvent>)>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Action<'a> {
-    Play(&'a CardDetails, Flank),
+    Play(Card<'a>, Flank),
     Reap(usize),
     Fight(usize, usize),
 }
 
+// What a card lets the active player pick. Sides are from the active
+// player's point of view.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Target {
+    Creature(Side, usize),
+    Artifact(Side, usize),
+    // A card in the active player's hand
+    Hand(usize),
+}
+
+// What a target is being picked for
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Choice {
+    // A friendly creature to capture amber onto
+    Capture,
+    // A creature to deal damage to
+    Damage,
+    // A card to discard, if any
+    Discard,
+    // An enemy creature or artifact to take control of
+    TakeControl,
+}
+
+impl Choice {
+    // Whether the player can pass instead of picking a target
+    pub fn optional(self) -> bool {
+        self == Choice::Discard
+    }
+}
+
+// What a player wants to do next in their turn
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Decision<'a> {
+    Act(Action<'a>),
+    // Take back the last action, or redo the last one taken back
+    Undo,
+    Redo,
+    EndTurn,
+}
+
 
 impl<'a> Turn<'a> {
-    fn new(mine: DeckBoard<'a>, opponent: DeckBoard<'a>, player: Box<dyn Player>) -> Self {
-        let house = player.choose_house(&mine, &opponent);
+    pub fn new(mine: DeckBoard<'a>, opponent: DeckBoard<'a>, player: Box<dyn Player>) -> Self {
+        let house = player.choose_house(&PlayerView::choosing(&mine, &opponent));
+        Turn::resume(mine, opponent, house, player)
+    }
+
+    // Picks up a turn where the house has already been chosen
+    pub fn resume(mine: DeckBoard<'a>, opponent: DeckBoard<'a>, house: House, player: Box<dyn Player>) -> Self {
         Turn {
             mine,
             opponent,
             house,
             player,
+            triggers: TriggerQueue::new(),
+            played: 0,
+            log: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
         }
     }
 
-    pub fn can_play(&self, card_details: &'a CardDetails) -> bool {
-        card_details.house == self.house
+    // Starts logging events, with the active player in `seat`
+    pub fn record(&mut self, seat: usize) {
+        self.log = Some((seat, Vec::new()));
     }
 
-    fn play(&mut self, card: &'a CardDetails, from_hand: bool, flank: Flank) {
+    // Hands over the events logged since last time
+    pub fn take_events(&mut self) -> Vec<LogEvent> {
+        match self.log {
+            Some((_, ref mut events)) => mem::take(events),
+            None => Vec::new(),
+        }
+    }
+
+    fn log<F>(&mut self, side: Side, event: F) where F: FnOnce(usize) -> LogEvent {
+        if let Some((seat, ref mut events)) = self.log {
+            let seat = match side {
+                Side::Mine => seat,
+                Side::Opponent => 1 - seat,
+            };
+            events.push(event(seat));
+        }
+    }
+
+    pub fn mine(&self) -> &DeckBoard<'a> {
+        &self.mine
+    }
+
+    pub fn opponent(&self) -> &DeckBoard<'a> {
+        &self.opponent
+    }
+
+    pub fn house(&self) -> House {
+        self.house.clone()
+    }
+
+    pub fn cards_played(&self) -> u32 {
+        self.played
+    }
+
+    // What the active player gets to see of the turn
+    pub fn view<'b>(&'b self) -> PlayerView<'a, 'b> {
+        let mut view = PlayerView::new(&self.mine, &self.opponent, Some(self.house.clone()), self.legal_actions());
+        view.undos = self.undo.len();
+        view.redos = self.redo.len();
+        view
+    }
+
+    fn board(&self, side: Side) -> &DeckBoard<'a> {
+        match side {
+            Side::Mine => &self.mine,
+            Side::Opponent => &self.opponent,
+        }
+    }
+
+    fn board_mut(&mut self, side: Side) -> &mut DeckBoard<'a> {
+        self.boards_mut(side).0
+    }
+
+    // Returns the board for the given side, followed by the other board
+    fn boards_mut(&mut self, side: Side) -> (&mut DeckBoard<'a>, &mut DeckBoard<'a>) {
+        match side {
+            Side::Mine => (&mut self.mine, &mut self.opponent),
+            Side::Opponent => (&mut self.opponent, &mut self.mine),
+        }
+    }
+
+    pub fn can_play(&self, card: Card<'a>) -> bool {
+        card.house == self.house
+    }
+
+    // Every action the active player could take right now
+    pub fn legal_actions(&self) -> Vec<Action<'a>> {
+        let mut actions = Vec::new();
+        let mut seen: Vec<(&'a CardDetails, &'a [BonusIcon])> = Vec::new();
+        for &card in &self.mine.hand {
+            // Copies of the same card play out the same way, unless they
+            // were enhanced differently
+            let kind = (card.details, card.enhancements);
+            if !self.can_play(card) || seen.contains(&kind) {
+                continue;
+            }
+            seen.push(kind);
+            actions.push(Action::Play(card, Flank::Right));
+            if card.card_type.is_creature() && !self.mine.creatures.is_empty() {
+                actions.push(Action::Play(card, Flank::Left));
+            }
+        }
+
+        for (index, creature) in self.mine.creatures.iter().enumerate() {
+            if creature.exhausted || creature.details.house != self.house {
+                continue;
+            }
+            actions.push(Action::Reap(index));
+            for target in 0..self.opponent.creatures.len() {
+                actions.push(Action::Fight(index, target));
+            }
+        }
+        actions
+    }
+
+    // Resolves anything that happens at the start of the active player's
+    // turn. Cards on both sides start counting what happens to them afresh.
+    pub fn start(&mut self) {
+        self.mine.start_of_turn();
+        self.opponent.start_of_turn();
+        self.raise(Event::StartOfTurn(Side::Mine));
+    }
+
+    fn play(&mut self, card: Card<'a>, from_hand: bool, flank: Flank) {
         if self.can_play(card) {
-            self.mine.play(card, from_hand, flank);
+            if from_hand {
+                self.log(Side::Mine, |seat| LogEvent::Play { seat, card: card.id.clone(), slot: card.slot, flank });
+            }
+            self.played += 1;
+            let amber = card.amber + card.bonus_count(&BonusIcon::Amber);
+            if amber > 0 {
+                self.log(Side::Mine, |seat| LogEvent::Amber { seat, card: card.id.clone(), amount: amber });
+            }
+            // Cards drawn by bonus icons end up at the back of the hand
+            let kept = self.mine.hand.len() - if from_hand { 1 } else { 0 };
+            let source = self.mine.play(card, from_hand, flank);
+            let drawn = self.mine.hand[kept.min(self.mine.hand.len())..].to_vec();
+            for drawn_card in drawn {
+                self.log(Side::Mine, |seat| LogEvent::draw(seat, &drawn_card));
+            }
+            if card.card_type == Type::Action {
+                self.log(Side::Mine, |seat| LogEvent::Discard { seat, card: card.id.clone() });
+            }
+            self.resolve_bonus_icons(card, source);
+            if let Some(source) = source {
+                self.raise(Event::Played(Side::Mine, source));
+            }
+        }
+    }
+
+    // Resolves the capture and damage icons of a card just played. Its amber
+    // and draw icons were resolved by the board.
+    fn resolve_bonus_icons(&mut self, card: Card<'a>, source: Option<Source>) {
+        for _ in 0..card.bonus_count(&BonusIcon::Capture) {
+            if self.opponent.amber == 0 {
+                break;
+            }
+            // Any friendly creature can capture, the card's own creature is listed first
+            let mut captors: Vec<usize> = (0..self.mine.creatures.len()).collect();
+            if let Some(Source::Creature(index)) = source {
+                captors.retain(|&captor| captor != index);
+                captors.insert(0, index);
+            }
+            let targets = captors.into_iter().map(|index| Target::Creature(Side::Mine, index)).collect();
+            if let Some(Target::Creature(_, index)) = self.choose_target(Choice::Capture, targets) {
+                self.opponent.amber -= 1;
+                self.mine.creatures[index].captured += 1;
+            }
+        }
+        for _ in 0..card.bonus_count(&BonusIcon::Damage) {
+            // Enemy creatures are listed first
+            let targets = [Side::Opponent, Side::Mine].iter()
+                .flat_map(|&side| (0..self.board(side).creatures.len()).map(move |index| Target::Creature(side, index)))
+                .collect();
+            if let Some(Target::Creature(side, index)) = self.choose_target(Choice::Damage, targets) {
+                let (amount, alive) = {
+                    let creature = &mut self.board_mut(side).creatures[index];
+                    let before = creature.damage;
+                    creature.do_damage(1);
+                    (creature.damage - before, creature.is_alive())
+                };
+                if amount > 0 {
+                    self.log(side, |seat| LogEvent::Damage { seat, creature: index, amount });
+                }
+                if !alive {
+                    self.destroy_all(&[(side, index, Some(card))]);
+                }
+            }
+        }
+        for _ in 0..card.bonus_count(&BonusIcon::Discard) {
+            let targets = (0..self.mine.hand.len()).map(Target::Hand).collect();
+            if let Some(Target::Hand(index)) = self.choose_target(Choice::Discard, targets) {
+                let discarded = self.mine.hand[index];
+                self.mine.discard(index);
+                self.log(Side::Mine, |seat| LogEvent::Discard { seat, card: discarded.id.clone() });
+            }
+        }
+        for icon in card.details.bonus_icons.iter().chain(card.enhancements) {
+            if let BonusIcon::Unknown(ref name) = *icon {
+                warn!("{} bonus icons not yet implemented", name);
+            }
         }
     }
 
-    fn reap<'b>(&mut self, index: usize) {
-        let creature = &mut self.mine.creatures[index];
-        assert!(!creature.exhausted);
-        creature.exhausted = true;
+    // Has the player pick one of the targets, and logs what they picked.
+    // There is nothing to pick from a single target unless the player can
+    // pass instead. Returns None if there are no targets or the player passed.
+    fn choose_target(&mut self, choice: Choice, targets: Vec<Target>) -> Option<Target> {
+        if targets.is_empty() || (targets.len() == 1 && !choice.optional()) {
+            return targets.first().cloned();
+        }
+        let picked = match self.player.choose_target(&self.view(), choice, &targets) {
+            Some(index) if index < targets.len() => Some(index),
+            _ if choice.optional() => None,
+            // Anything else takes the first target
+            _ => Some(0),
+        };
+        self.log(Side::Mine, |seat| LogEvent::ChooseTarget { seat, target: picked });
+        picked.map(|index| targets[index])
+    }
+
+    fn reap(&mut self, index: usize) {
+        let card = {
+            let creature = &mut self.mine.creatures[index];
+            assert!(!creature.exhausted);
+            creature.on_reaped();
+            creature.details
+        };
         self.mine.amber += 1;
+        self.log(Side::Mine, |seat| LogEvent::Reap { seat, creature: index });
+        self.log(Side::Mine, |seat| LogEvent::Amber { seat, card: card.id.clone(), amount: 1 });
+        self.raise(Event::Reaped(Side::Mine, index));
     }
 
     fn fight(&mut self, my_index: usize, target_index: usize) {
         assert!(!self.mine.creatures[my_index].exhausted);
+        self.log(Side::Mine, |seat| LogEvent::Fight { seat, creature: my_index, target: target_index });
+        let target = self.opponent.creatures[target_index].details;
+        self.raise(Event::BeforeFight(Side::Mine, my_index));
+        // "Before Fight:" abilities can take the target away, the attacker is
+        // used either way
+        let target_index = if self.opponent.creatures.get(target_index).map(|c| c.details) == Some(target) {
+            target_index
+        } else {
+            match self.opponent.creatures.iter().position(|c| c.details == target) {
+                Some(index) => index,
+                None => {
+                    self.mine.creatures[my_index].exhausted = true;
+                    return;
+                }
+            }
+        };
+        let damage = (self.mine.creatures[my_index].damage, self.opponent.creatures[target_index].damage);
         {
-            self.mine.creatures[my_index].exhausted = true;
+            self.mine.creatures[my_index].on_before_fight();
             self.mine.creatures[my_index].fight(&mut self.opponent.creatures[target_index]);
+            self.mine.creatures[my_index].on_after_fight();
+            self.opponent.creatures[target_index].on_after_fight();
         }
-
-        if !self.mine.creatures[my_index].is_alive() {
-            let destroyed_creature = self.mine.destroy_creature(my_index);
-            self.mine.discard.push(destroyed_creature);
+        for &(side, index, before) in &[(Side::Mine, my_index, damage.0), (Side::Opponent, target_index, damage.1)] {
+            let amount = self.board(side).creatures[index].damage - before;
+            if amount > 0 {
+                self.log(side, |seat| LogEvent::Damage { seat, creature: index, amount });
+            }
         }
 
+        // Everything that died in the fight is destroyed at the same time
+        let attacker = self.mine.creatures[my_index].details;
+        let defender = self.opponent.creatures[target_index].details;
+        let attacker_survived = self.mine.creatures[my_index].is_alive();
+        let mut dying = Vec::new();
+        if !attacker_survived {
+            dying.push((Side::Mine, my_index, Some(defender)));
+        }
         if !self.opponent.creatures[target_index].is_alive() {
-            let destroyed_creature = self.opponent.destroy_creature(target_index);
-            self.opponent.discard.push(destroyed_creature);
+            dying.push((Side::Opponent, target_index, Some(attacker)));
+        }
+        self.destroy_all(&dying);
+
+        // "Fight:" abilities only resolve if the attacker survives
+        if attacker_survived {
+            self.raise(Event::Fought(Side::Mine, my_index));
+        }
+    }
+
+    // Destroys a creature, resolving everything that goes with it.
+    // Returns false if the destruction was prevented.
+    #[cfg(test)]
+    fn destroy(&mut self, side: Side, index: usize) -> bool {
+        self.destroy_all(&[(side, index, None)]) == 1
+    }
+
+    // Destroys several creatures simultaneously: their "Destroyed:" abilities
+    // are ordered and resolved as one batch before any of them leave play.
+    // Each target comes with the creature that killed it in a fight, if any.
+    // Returns how many were actually destroyed.
+    fn destroy_all(&mut self, targets: &[(Side, usize, Option<Card<'a>>)]) -> usize {
+        let mut destroyed = Vec::new();
+        for &(side, index, by) in targets {
+            let creature = &mut self.board_mut(side).creatures[index];
+            if creature.destroyed {
+                // Already on its way out as part of this or an earlier batch
+                continue;
+            } else if creature.warded {
+                creature.warded = false;
+            } else {
+                creature.on_destroyed();
+                destroyed.push((side, index, creature.details));
+                let card = creature.details.id.clone();
+                let by = by.map(|killer| killer.id.clone());
+                self.log(side, |seat| LogEvent::Destroyed { seat, creature: index, card, by });
+            }
+        }
+
+        // "Destroyed:" abilities resolve while the creatures are still in play
+        let events: Vec<Event> = destroyed.iter()
+            .map(|&(side, index, _)| Event::Destroyed(side, index))
+            .collect();
+        self.raise_all(&events);
+
+        // The abilities can move other creatures around, so find each one
+        // again if it isn't where it was
+        let mut leaving = Vec::new();
+        for &(side, index, card) in &destroyed {
+            let creatures = &self.board(side).creatures;
+            let is_leaving = |i: usize| {
+                creatures[i].destroyed && creatures[i].details == card && !leaving.contains(&(side, i))
+            };
+            let found = if index < creatures.len() && is_leaving(index) {
+                Some(index)
+            } else {
+                (0..creatures.len()).find(|&i| is_leaving(i))
+            };
+            leaving.extend(found.map(|i| (side, i)));
+        }
+        // Remove from the back so earlier indices stay valid
+        leaving.sort_by_key(|&(_, index)| Reverse(index));
+        let mut discarded = Vec::new();
+        for &(side, index) in &leaving {
+            let (board, other) = self.boards_mut(side);
+            let (captured, upgrades, zone) = {
+                let creature = &mut board.creatures[index];
+                let captured = mem::replace(&mut creature.captured, 0);
+                let upgrades = mem::take(&mut creature.upgrades);
+                (captured, upgrades, creature.leaves_to)
+            };
+            // Captured amber goes to the controller's opponent, the cards to their owners
+            other.amber += captured;
+            let owner = board.creatures[index].owner;
+            let destroyed_creature = board.destroy_creature(index);
+            let owner_side = |owner| match owner {
+                Owner::Controller => side,
+                Owner::Opponent => side.other(),
+            };
+            for upgrade in upgrades {
+                discarded.push((owner_side(upgrade.owner), upgrade.card));
+            }
+            if zone == Zone::Discard {
+                discarded.push((owner_side(owner), destroyed_creature));
+            } else {
+                self.board_mut(owner_side(owner)).put_in(zone, destroyed_creature);
+            }
         }
+        for (side, card) in discarded {
+            self.board_mut(side).discard.push(card);
+            self.log(side, |seat| LogEvent::Discard { seat, card: card.id.clone() });
+        }
+        leaving.len()
+    }
+
+    // Moves a creature from the other side of the table to a flank of
+    // `side`. It keeps its damage, captured amber and upgrades, and it and
+    // its upgrades still go to their owners' zones when they leave play.
+    pub fn take_control(&mut self, side: Side, index: usize, flank: Flank) -> usize {
+        let mut creature = self.board_mut(side.other()).remove_creature(index);
+        creature.on_control_changed();
+        let card = creature.details;
+        self.log(side, |seat| LogEvent::Control { seat, card: card.id.clone() });
+        self.board_mut(side).place_creature(creature, flank)
+    }
+
+    // Moves an artifact from the other side of the table to `side`. It still
+    // goes to its owner's zones when it leaves play.
+    pub fn take_artifact_control(&mut self, side: Side, index: usize) -> usize {
+        let mut artifact = self.board_mut(side.other()).artifacts.remove(index);
+        artifact.on_control_changed();
+        let card = artifact.details;
+        self.log(side, |seat| LogEvent::Control { seat, card: card.id.clone() });
+        let board = self.board_mut(side);
+        board.artifacts.push(artifact);
+        board.artifacts.len() - 1
     }
 
-    fn execute_action(&mut self, action: Action<'a>) {
+    fn raise(&mut self, event: Event) {
+        self.raise_all(&[event]);
+    }
+
+    // Collects every ability in play that goes off for the simultaneous
+    // events, lets the player order them if there is more than one, and
+    // resolves them.
+    fn raise_all(&mut self, events: &[Event]) {
+        let mut batch = Vec::new();
+        for &event in events {
+            for &side in &[Side::Mine, Side::Opponent] {
+                let board = self.board(side);
+                collect_triggers(&board.creatures, event, side, Source::Creature, &mut batch);
+                collect_triggers(&board.artifacts, event, side, Source::Artifact, &mut batch);
+            }
+        }
+        if batch.is_empty() {
+            return;
+        }
+        let order = if batch.len() > 1 {
+            let order = self.player.order_triggers(&self.view(), &batch);
+            self.log(Side::Mine, |seat| LogEvent::OrderTriggers { seat, order: order.clone() });
+            order
+        } else {
+            vec![0]
+        };
+        self.triggers.push_ordered(batch, &order);
+        self.resolve_triggers();
+    }
+
+    fn resolve_triggers(&mut self) {
+        while let Some(trigger) = self.triggers.pop() {
+            self.resolve(trigger);
+        }
+    }
+
+    fn resolve(&mut self, trigger: Trigger) {
+        let mut drawn = Vec::new();
+        let mut gained = 0;
+        let mut takeable = Vec::new();
+        let (board, other) = self.boards_mut(trigger.side);
+        let source = match trigger.source {
+            Source::Creature(index) => board.creatures.get(index).map(|c| c.details),
+            Source::Artifact(index) => board.artifacts.get(index).map(|a| a.details),
+        };
+        match trigger.effect {
+            Effect::GainAmber(amount) => {
+                board.amber += amount;
+                gained = amount;
+            }
+            Effect::StealAmber(amount) => {
+                let stolen = min(amount, other.amber);
+                other.amber -= stolen;
+                board.amber += stolen;
+                gained = stolen;
+            }
+            Effect::DrawCards(count) => {
+                drawn.extend((0..count).filter_map(|_| board.draw_card()));
+            }
+            Effect::Capture(amount) => {
+                if let Source::Creature(index) = trigger.source {
+                    let captured = min(amount, other.amber);
+                    other.amber -= captured;
+                    board.creatures[index].captured += captured;
+                }
+            }
+            Effect::LeavePlayTo(zone) => {
+                if let Source::Creature(index) = trigger.source {
+                    board.creatures[index].leaves_to = zone;
+                }
+            }
+            Effect::TakeCreature => {
+                // Creatures already being destroyed stay where they are
+                let side = trigger.side.other();
+                takeable = (0..other.creatures.len())
+                    .filter(|&index| !other.creatures[index].destroyed)
+                    .map(|index| Target::Creature(side, index))
+                    .collect();
+            }
+            Effect::TakeArtifact => {
+                let side = trigger.side.other();
+                takeable = (0..other.artifacts.len()).map(|index| Target::Artifact(side, index)).collect();
+            }
+        }
+        // The active player makes the choice, whichever side the trigger is on
+        match self.choose_target(Choice::TakeControl, takeable) {
+            Some(Target::Creature(_, index)) => {
+                self.take_control(trigger.side, index, Flank::Right);
+            }
+            Some(Target::Artifact(_, index)) => {
+                self.take_artifact_control(trigger.side, index);
+            }
+            _ => {}
+        }
+        if let (Some(card), true) = (source, gained > 0) {
+            self.log(trigger.side, |seat| LogEvent::Amber { seat, card: card.id.clone(), amount: gained });
+        }
+        for card in drawn {
+            self.log(trigger.side, |seat| LogEvent::draw(seat, &card));
+        }
+    }
+
+    fn checkpoint(&self) -> Checkpoint<'a> {
+        Checkpoint {
+            mine: self.mine.clone(),
+            opponent: self.opponent.clone(),
+            played: self.played,
+            log: self.log.clone(),
+        }
+    }
+
+    // Puts the turn back to the checkpoint, returning the state it was in
+    fn restore(&mut self, checkpoint: Checkpoint<'a>) -> Checkpoint<'a> {
+        Checkpoint {
+            mine: mem::replace(&mut self.mine, checkpoint.mine),
+            opponent: mem::replace(&mut self.opponent, checkpoint.opponent),
+            played: mem::replace(&mut self.played, checkpoint.played),
+            log: mem::replace(&mut self.log, checkpoint.log),
+        }
+    }
+
+    // Like execute_action, but the action can be undone until the turn ends
+    pub fn apply(&mut self, action: Action<'a>) {
+        let checkpoint = self.checkpoint();
+        self.undo.push(checkpoint);
+        self.redo.clear();
+        self.execute_action(action);
+    }
+
+    // Reverts the last applied action. Returns false if there was none.
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(checkpoint) => {
+                let undone = self.restore(checkpoint);
+                self.redo.push(undone);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Applies the last undone action again. Returns false if there was none.
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(checkpoint) => {
+                let redone = self.restore(checkpoint);
+                self.undo.push(redone);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn execute_action(&mut self, action: Action<'a>) {
         match action {
             Action::Play(details, flank) => self.play(details, true, flank),
             Action::Reap(index) => self.reap(index),
@@ -71,35 +639,120 @@ impl<'a> Turn<'a> {
         }
     }
 
+    // Plays out the rest of the turn as the player decides. Actions can only
+    // be undone for players that can ask to, everyone else skips the
+    // checkpoints.
     pub fn run(&mut self) {
-        while let Some(action) = self.player.next_action(self) {
-            self.execute_action(action);
+        let undoable = self.player.can_undo();
+        loop {
+            match self.player.next_decision(&self.view()) {
+                Decision::Act(action) if undoable => self.apply(action),
+                Decision::Act(action) => self.execute_action(action),
+                Decision::Undo => {
+                    self.undo();
+                }
+                Decision::Redo => {
+                    self.redo();
+                }
+                Decision::EndTurn => break,
+            }
         }
     }
 
     pub fn end(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
         self.mine.my_turn_over();
         self.mine.turn_over();
         self.opponent.turn_over();
+        self.log(Side::Mine, |seat| LogEvent::EndTurn { seat });
+        for card in self.mine.draw_to(6) {
+            self.log(Side::Mine, |seat| LogEvent::draw(seat, &card));
+        }
+    }
+
+    // Hands back the active board, the opponent's board and the player
+    pub fn finish(self) -> (DeckBoard<'a>, DeckBoard<'a>, Box<dyn Player>) {
+        (self.mine, self.opponent, self.player)
+    }
+}
+
+fn collect_triggers<C, F>(cards: &[C], event: Event, side: Side, source: F, batch: &mut Vec<Trigger>)
+    where C: FieldCard, F: Fn(usize) -> Source {
+    for (index, card) in cards.iter().enumerate() {
+        for ability in card.abilities() {
+            if ability.responds_to(event, side, source(index)) {
+                batch.push(Trigger { side, source: source(index), effect: ability.effect });
+            }
+        }
     }
 }
 
 #[cfg(test)]
 pub mod test {
-    use super::{Turn, Action};
+    use super::{Turn, Action, Choice, Target};
     use api::test::deck_from;
     use game::CardDetails;
     use board::DeckBoard;
     use player::test::TestPlayer;
     use game::test::test_card;
-    use game::{House, Type, Flank};
+    use game::{BonusIcon, House, Type, Flank};
+    use board::Zone;
+    use field::{Artifact, Owner, Upgrade};
+    use player::Player;
+    use trigger::{Ability, Effect, Side, Trigger, When};
+    use rand::Rng;
+    use replay::LogEvent;
+    use snapshot::BoardSnapshot;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use view::PlayerView;
+
+    // Resolves simultaneous triggers in reverse and counts how often it was asked
+    struct ReversingPlayer {
+        asked: Rc<Cell<u32>>,
+    }
+
+    impl Player for ReversingPlayer {
+        fn choose_house(&self, _view: &PlayerView) -> House {
+            House::Brobnar
+        }
+
+        fn next_action<'a, 'b>(&self, _view: &PlayerView<'a, 'b>) -> Option<Action<'a>> {
+            None
+        }
+
+        fn order_triggers(&self, _view: &PlayerView, triggers: &[Trigger]) -> Vec<usize> {
+            self.asked.set(self.asked.get() + 1);
+            (0..triggers.len()).rev().collect()
+        }
+    }
+
+    // Always picks the same target
+    struct TargetingPlayer {
+        pick: Option<usize>,
+    }
+
+    impl Player for TargetingPlayer {
+        fn choose_house(&self, _view: &PlayerView) -> House {
+            House::Brobnar
+        }
+
+        fn next_action<'a, 'b>(&self, _view: &PlayerView<'a, 'b>) -> Option<Action<'a>> {
+            None
+        }
+
+        fn choose_target(&self, _view: &PlayerView, _choice: Choice, _targets: &[Target]) -> Option<usize> {
+            self.pick
+        }
+    }
 
-    fn board_fixture<F>(details_a: CardDetails, details_b: CardDetails, tf: F) where F: Fn(&mut Turn) {
+    pub fn board_fixture<F>(details_a: CardDetails, details_b: CardDetails, tf: F) where F: Fn(&mut Turn) {
         let deck_a = deck_from(details_a, 36);
         let deck_b = deck_from(details_b, 36);
-        let mut board_a = DeckBoard::new(&deck_a);
-        let mut board_b = DeckBoard::new(&deck_b);
-        let player = TestPlayer { house: *deck_a.houses.first().unwrap() };
+        let board_a = DeckBoard::new(&deck_a);
+        let board_b = DeckBoard::new(&deck_b);
+        let player = TestPlayer { house: deck_a.houses[0].clone() };
         let mut turn = Turn::new(board_a, board_b, Box::new(player));
         tf(&mut turn);
     }
@@ -118,6 +771,86 @@ pub mod test {
         });
     }
 
+    #[test]
+    fn test_turn_play_bonus_icons() {
+        let mut card = test_card(House::Brobnar, Type::Creature, 1, 2, 0);
+        card.bonus_icons = vec![BonusIcon::Amber, BonusIcon::Draw];
+        board_fixture(card, test_card(House::Dis, Type::Creature, 0, 1, 0), |turn| {
+            let enemy = turn.opponent.hand[0];
+            turn.opponent.play(enemy, true, Flank::Right);
+            turn.opponent.amber = 2;
+            turn.record(0);
+            // This copy was enhanced, on top of the printed icons
+            turn.mine.hand[0].enhancements = &[BonusIcon::Capture, BonusIcon::Damage];
+            let card = turn.mine.hand[0];
+            turn.execute_action(Action::Play(card, Flank::Right));
+            assert_eq!(turn.mine.amber, 2);
+            assert_eq!(turn.mine.hand.len(), 6);
+            assert_eq!(turn.mine.creatures[0].captured, 1);
+            assert_eq!(turn.opponent.amber, 1);
+            assert!(turn.opponent.creatures.is_empty());
+            let events = turn.take_events();
+            assert!(events.contains(&LogEvent::Amber { seat: 0, card: card.id.clone(), amount: 2 }));
+            assert!(events.contains(&LogEvent::Destroyed { seat: 1, creature: 0, card: enemy.id.clone(),
+                                                          by: Some(card.id.clone()) }));
+        });
+    }
+
+    #[test]
+    fn test_turn_bonus_icon_targets() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        board_fixture(card.clone(), card, |turn| {
+            for _ in 0..2 {
+                let enemy = turn.opponent.hand[0];
+                turn.opponent.play(enemy, true, Flank::Right);
+            }
+            turn.player = Box::new(TargetingPlayer { pick: Some(1) });
+            turn.record(0);
+            turn.mine.hand[0].enhancements = &[BonusIcon::Damage];
+            let card = turn.mine.hand[0];
+            turn.execute_action(Action::Play(card, Flank::Right));
+            // Enemy creatures are offered first, then the new creature
+            assert_eq!(turn.opponent.creatures[0].damage, 0);
+            assert_eq!(turn.opponent.creatures[1].damage, 1);
+            let events = turn.take_events();
+            assert!(events.contains(&LogEvent::ChooseTarget { seat: 0, target: Some(1) }));
+            assert!(events.contains(&LogEvent::Damage { seat: 1, creature: 1, amount: 1 }));
+
+            // Out of range picks take the first target
+            turn.player = Box::new(TargetingPlayer { pick: Some(9) });
+            turn.mine.hand[0].enhancements = &[BonusIcon::Damage];
+            let card = turn.mine.hand[0];
+            turn.execute_action(Action::Play(card, Flank::Right));
+            assert_eq!(turn.opponent.creatures[0].damage, 1);
+        });
+    }
+
+    #[test]
+    fn test_turn_bonus_icon_discard() {
+        let card = test_card(House::Brobnar, Type::Action, 1, 0, 0);
+        board_fixture(card.clone(), card, |turn| {
+            turn.record(0);
+            turn.player = Box::new(TargetingPlayer { pick: None });
+            turn.mine.hand[0].enhancements = &[BonusIcon::Discard];
+            let card = turn.mine.hand[0];
+            turn.execute_action(Action::Play(card, Flank::Right));
+            // Discarding is optional
+            assert_eq!(turn.mine.hand.len(), 5);
+            assert_eq!(turn.mine.discard, vec![card]);
+
+            turn.player = Box::new(TargetingPlayer { pick: Some(2) });
+            turn.mine.hand[0].enhancements = &[BonusIcon::Discard];
+            let card = turn.mine.hand[0];
+            let discarded = turn.mine.hand[3];
+            turn.execute_action(Action::Play(card, Flank::Right));
+            assert_eq!(turn.mine.hand.len(), 3);
+            assert_eq!(turn.mine.discard[2], discarded);
+            let events = turn.take_events();
+            assert!(events.contains(&LogEvent::ChooseTarget { seat: 0, target: None }));
+            assert!(events.contains(&LogEvent::Discard { seat: 0, card: discarded.id.clone() }));
+        });
+    }
+
     #[test]
     fn test_turn_reap_creature() {
         let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
@@ -156,4 +889,418 @@ pub mod test {
             assert!(turn.mine.creatures[0].exhausted);
         });
     }
+
+    #[test]
+    fn test_turn_reap_triggers() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        board_fixture(card.clone(), card, |turn| {
+            let asked = Rc::new(Cell::new(0));
+            turn.player = Box::new(ReversingPlayer { asked: asked.clone() });
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            turn.mine.creatures[0].abilities.push(Ability::new(When::Reap, Effect::GainAmber(2)));
+            let mut artifact = Artifact::new(card);
+            artifact.abilities.push(Ability::new(When::AfterFriendlyReap, Effect::StealAmber(2)));
+            turn.mine.artifacts.push(artifact);
+            turn.opponent.amber = 1;
+            turn.end();
+
+            turn.execute_action(Action::Reap(0));
+            assert_eq!(turn.mine.amber, 4);
+            assert_eq!(turn.opponent.amber, 0);
+            assert_eq!(asked.get(), 1);
+            assert!(turn.triggers.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_turn_single_trigger_not_ordered() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        board_fixture(card.clone(), card, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            turn.mine.creatures[0].abilities.push(Ability::new(When::Reap, Effect::DrawCards(1)));
+            turn.end();
+
+            let hand = turn.mine.hand.len();
+            turn.execute_action(Action::Reap(0));
+            assert_eq!(turn.mine.hand.len(), hand + 1);
+        });
+    }
+
+    #[test]
+    fn test_turn_lifecycle_triggers() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        board_fixture(card.clone(), card, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            turn.mine.creatures[0].abilities.push(Ability::new(When::StartOfTurn, Effect::GainAmber(1)));
+            turn.mine.creatures[0].abilities.push(Ability::new(When::Destroyed, Effect::GainAmber(2)));
+            turn.start();
+            assert_eq!(turn.mine.amber, 1);
+
+            let &card2 = turn.opponent.hand.first().unwrap();
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.opponent.creatures[0].abilities.push(Ability::new(When::Destroyed, Effect::GainAmber(1)));
+            turn.end();
+            turn.execute_action(Action::Fight(0, 0));
+            assert_eq!(turn.mine.amber, 3);
+            assert_eq!(turn.opponent.amber, 1);
+            assert_eq!(turn.mine.discard.len(), 1);
+            assert_eq!(turn.opponent.discard.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_turn_lifecycle_hooks() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 3, 1);
+        board_fixture(card, test_card(House::Dis, Type::Creature, 0, 2, 2), |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            let &card2 = turn.opponent.hand.first().unwrap();
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.end();
+
+            // Armor soaks up part of each hit
+            turn.execute_action(Action::Fight(0, 0));
+            for creature in &[&turn.mine.creatures[0], &turn.opponent.creatures[0]] {
+                assert!(creature.fought);
+                assert_eq!((creature.damage, creature.damage_taken), (1, 1));
+            }
+
+            turn.start();
+            for creature in &[&turn.mine.creatures[0], &turn.opponent.creatures[0]] {
+                assert!(!creature.fought);
+                assert_eq!((creature.damage, creature.damage_taken), (1, 0));
+            }
+
+            // A creature is only destroyed once, however often it is hit
+            assert_eq!(turn.destroy_all(&[(Side::Mine, 0, None), (Side::Mine, 0, None)]), 1);
+            assert!(turn.mine.creatures.is_empty());
+            assert_eq!(turn.mine.discard.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_turn_fight_trigger_needs_survivor() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        board_fixture(card.clone(), card, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            turn.mine.creatures[0].abilities.push(Ability::new(When::Fight, Effect::GainAmber(1)));
+            turn.mine.creatures[0].abilities.push(Ability::new(When::BeforeFight, Effect::GainAmber(1)));
+            let &card2 = turn.opponent.hand.first().unwrap();
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.end();
+
+            turn.execute_action(Action::Fight(0, 0));
+            assert_eq!(turn.mine.creatures.len(), 0);
+            assert_eq!(turn.mine.amber, 1);
+        });
+    }
+
+    #[test]
+    fn test_turn_destroy_pipeline() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        let card2 = test_card(House::Brobnar, Type::Creature, 0, 3, 0);
+        board_fixture(card, card2, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            turn.mine.creatures[0].abilities.push(Ability::new(When::Reap, Effect::Capture(2)));
+            turn.mine.creatures[0].abilities.push(Ability::new(When::Destroyed, Effect::LeavePlayTo(Zone::Archives)));
+            turn.mine.creatures[0].upgrades.push(Upgrade { card, owner: Owner::Controller });
+            turn.opponent.amber = 3;
+            turn.end();
+            turn.execute_action(Action::Reap(0));
+            assert_eq!(turn.mine.creatures[0].captured, 2);
+            assert_eq!(turn.opponent.amber, 1);
+
+            assert!(turn.destroy(Side::Mine, 0));
+            assert_eq!(turn.mine.creatures.len(), 0);
+            assert_eq!(turn.opponent.amber, 3);
+            assert_eq!(turn.mine.discard.len(), 1);
+            assert_eq!(turn.mine.archives.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_turn_destroy_warded() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        board_fixture(card.clone(), card, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            turn.mine.creatures[0].abilities.push(Ability::new(When::Destroyed, Effect::GainAmber(1)));
+            turn.mine.creatures[0].warded = true;
+
+            assert!(!turn.destroy(Side::Mine, 0));
+            assert_eq!(turn.mine.creatures.len(), 1);
+            assert!(!turn.mine.creatures[0].warded);
+            assert_eq!(turn.mine.amber, 0);
+
+            assert!(turn.destroy(Side::Mine, 0));
+            assert_eq!(turn.mine.creatures.len(), 0);
+            assert_eq!(turn.mine.amber, 1);
+            assert_eq!(turn.mine.discard.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_turn_fight_mutual_destruction() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        board_fixture(card.clone(), card, |turn| {
+            let asked = Rc::new(Cell::new(0));
+            turn.player = Box::new(ReversingPlayer { asked: asked.clone() });
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            turn.mine.creatures[0].abilities.push(Ability::new(When::Destroyed, Effect::GainAmber(1)));
+            let &card2 = turn.opponent.hand.first().unwrap();
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.opponent.creatures[1].abilities.push(Ability::new(When::Destroyed, Effect::StealAmber(1)));
+            turn.end();
+
+            turn.execute_action(Action::Fight(0, 1));
+            assert_eq!(asked.get(), 1);
+            // Reversed order: the steal resolves before the gain
+            assert_eq!(turn.mine.amber, 1);
+            assert_eq!(turn.opponent.amber, 0);
+            assert_eq!(turn.mine.creatures.len(), 0);
+            assert_eq!(turn.opponent.creatures.len(), 1);
+            assert_eq!(turn.mine.discard.len(), 1);
+            assert_eq!(turn.opponent.discard.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_turn_fight_elusive() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 3, 0);
+        board_fixture(card.clone(), card, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            turn.execute_action(Action::Play(card, Flank::Right));
+            let &card2 = turn.opponent.hand.first().unwrap();
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.opponent.creatures[0].elusive = true;
+            turn.end();
+
+            turn.execute_action(Action::Fight(0, 0));
+            assert_eq!(turn.mine.creatures[0].damage, 0);
+            assert_eq!(turn.opponent.creatures[0].damage, 0);
+
+            turn.execute_action(Action::Fight(1, 0));
+            assert_eq!(turn.mine.creatures.len(), 1);
+            assert_eq!(turn.opponent.creatures.len(), 0);
+
+            // Elusive resets once the turn is over
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.opponent.creatures[0].elusive = true;
+            turn.opponent.creatures[0].attacked = true;
+            turn.end();
+            assert!(!turn.opponent.creatures[0].attacked);
+        });
+    }
+
+    #[test]
+    fn test_turn_fight_skirmish() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        board_fixture(card.clone(), card, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            turn.mine.creatures[0].skirmish = true;
+            let &card2 = turn.opponent.hand.first().unwrap();
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.end();
+
+            turn.execute_action(Action::Fight(0, 0));
+            assert_eq!(turn.mine.creatures.len(), 1);
+            assert_eq!(turn.mine.creatures[0].damage, 0);
+            assert_eq!(turn.opponent.creatures.len(), 0);
+        });
+    }
+
+    #[test]
+    fn test_turn_fight_skirmish_into_elusive() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        board_fixture(card.clone(), card, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            turn.mine.creatures[0].skirmish = true;
+            turn.mine.creatures[0].abilities.push(Ability::new(When::Fight, Effect::GainAmber(1)));
+            let &card2 = turn.opponent.hand.first().unwrap();
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.opponent.creatures[0].elusive = true;
+            turn.end();
+
+            // Nobody takes damage, but the attacker still fought and survived
+            turn.execute_action(Action::Fight(0, 0));
+            assert_eq!(turn.opponent.creatures[0].damage, 0);
+            assert_eq!(turn.mine.creatures[0].damage, 0);
+            assert_eq!(turn.mine.amber, 1);
+        });
+    }
+
+    #[test]
+    fn test_turn_take_control() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 3, 0);
+        let card2 = test_card(House::Brobnar, Type::Creature, 0, 4, 0);
+        board_fixture(card, card2, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            let &card2 = turn.opponent.hand.first().unwrap();
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.opponent.creatures[1].damage = 1;
+            turn.opponent.creatures[1].captured = 2;
+            // One upgrade of their own, and one of mine they took earlier
+            turn.opponent.creatures[1].upgrades.push(Upgrade { card: card2, owner: Owner::Controller });
+            turn.opponent.creatures[1].upgrades.push(Upgrade { card, owner: Owner::Opponent });
+
+            let index = turn.take_control(Side::Mine, 1, Flank::Left);
+            assert_eq!(index, 0);
+            assert_eq!(turn.opponent.creatures.len(), 2);
+            assert!(turn.opponent.creatures.iter().all(|c| c.on_flank));
+            assert_eq!(turn.mine.creatures.len(), 2);
+            let taken = &turn.mine.creatures[0];
+            assert_eq!(taken.details, card2);
+            assert_eq!(taken.owner, Owner::Opponent);
+            assert_eq!(taken.damage, 1);
+            assert_eq!(taken.captured, 2);
+            assert_eq!(taken.upgrades[0].owner, Owner::Opponent);
+            assert_eq!(taken.upgrades[1].owner, Owner::Controller);
+            assert!(taken.on_flank);
+
+            // When it dies, each card goes back to its owner and the amber to its controller's opponent
+            assert!(turn.destroy(Side::Mine, 0));
+            assert_eq!(turn.mine.discard, vec![card]);
+            assert_eq!(turn.opponent.discard, vec![card2, card2]);
+            assert_eq!(turn.opponent.amber, 2);
+            assert_eq!(turn.mine.amber, 0);
+        });
+    }
+
+    #[test]
+    fn test_turn_take_control_effects() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 3, 0);
+        let card2 = test_card(House::Dis, Type::Artifact, 0, 0, 0);
+        board_fixture(card, card2, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            turn.execute_action(Action::Play(card, Flank::Right));
+            let &card2 = turn.opponent.hand.first().unwrap();
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.mine.creatures[0].abilities.push(Ability::new(When::Reap, Effect::TakeArtifact));
+            turn.end();
+            turn.record(0);
+
+            turn.execute_action(Action::Reap(0));
+            assert!(turn.opponent.artifacts.is_empty());
+            assert_eq!(turn.mine.artifacts.len(), 1);
+            assert_eq!(turn.mine.artifacts[0].owner, Owner::Opponent);
+
+            // A creature that takes control when destroyed doesn't get in the way of its own removal
+            turn.mine.creatures[1].abilities.push(Ability::new(When::Destroyed, Effect::TakeCreature));
+            turn.take_control(Side::Opponent, 0, Flank::Right);
+            assert_eq!(turn.opponent.creatures[0].owner, Owner::Opponent);
+            assert!(turn.destroy(Side::Mine, 0));
+            assert!(turn.opponent.creatures.is_empty());
+            assert_eq!(turn.mine.creatures.len(), 1);
+            assert_eq!(turn.mine.creatures[0].owner, Owner::Controller);
+            assert_eq!(turn.mine.discard.len(), 1);
+            assert!(turn.take_events().contains(&LogEvent::Control { seat: 0, card: card.id.clone() }));
+        });
+    }
+
+    // Everything about a board, down to the next number its rng gives
+    fn state(board: &DeckBoard) -> (BoardSnapshot, u64) {
+        (BoardSnapshot::new("", board), board.rng.clone().gen())
+    }
+
+    #[test]
+    fn test_turn_undo_redo() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        board_fixture(card.clone(), card, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            turn.execute_action(Action::Play(card, Flank::Right));
+            // Drawing everything reshuffles the discard, using the rng
+            turn.mine.creatures[0].abilities.push(Ability::new(When::Reap, Effect::DrawCards(40)));
+            turn.mine.discard.push(card);
+            let &card2 = turn.opponent.hand.first().unwrap();
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.end();
+            assert!(!turn.undo());
+
+            let before = (state(&turn.mine), state(&turn.opponent));
+            turn.apply(Action::Reap(0));
+            let after_reap = (state(&turn.mine), state(&turn.opponent));
+            assert_ne!(after_reap, before);
+            turn.apply(Action::Play(card, Flank::Left));
+            let after_play = (state(&turn.mine), state(&turn.opponent));
+
+            assert!(turn.undo());
+            assert_eq!((state(&turn.mine), state(&turn.opponent)), after_reap);
+            assert!(turn.undo());
+            assert_eq!((state(&turn.mine), state(&turn.opponent)), before);
+            assert!(!turn.undo());
+
+            assert!(turn.redo());
+            assert!(turn.redo());
+            assert_eq!((state(&turn.mine), state(&turn.opponent)), after_play);
+            assert!(!turn.redo());
+
+            // A new action replaces whatever was undone
+            assert!(turn.undo());
+            turn.apply(Action::Play(card, Flank::Right));
+            assert!(!turn.redo());
+            turn.end();
+            assert!(!turn.undo());
+        });
+    }
+
+    #[test]
+    fn test_turn_legal_actions() {
+        let card = test_card(House::Brobnar, Type::Creature, 0, 2, 0);
+        board_fixture(card.clone(), card, |turn| {
+            let &card = turn.mine.hand.first().unwrap();
+            assert_eq!(turn.legal_actions(), vec![Action::Play(card, Flank::Right)]);
+
+            turn.execute_action(Action::Play(card, Flank::Right));
+            // Only the first copy in hand is offered
+            let &card = turn.mine.hand.first().unwrap();
+            assert_eq!(turn.legal_actions(), vec![
+                Action::Play(card, Flank::Right),
+                Action::Play(card, Flank::Left),
+            ]);
+
+            let &card2 = turn.opponent.hand.first().unwrap();
+            turn.opponent.play(card2, true, Flank::Right);
+            turn.end();
+            let &card = turn.mine.hand.first().unwrap();
+            assert_eq!(turn.legal_actions(), vec![
+                Action::Play(card, Flank::Right),
+                Action::Play(card, Flank::Left),
+                Action::Reap(0),
+                Action::Fight(0, 0),
+            ]);
+
+            turn.house = House::Dis;
+            assert!(turn.legal_actions().is_empty());
+        });
+    }
+
+    #[test]
+    fn test_turn_legal_actions_enhanced() {
+        let card = test_card(House::Brobnar, Type::Action, 0, 0, 0);
+        board_fixture(card.clone(), card, |turn| {
+            // A plain and an enhanced copy are both offered, but a second
+            // copy enhanced the same way isn't
+            turn.mine.hand[1].enhancements = &[BonusIcon::Amber];
+            turn.mine.hand[2].enhancements = &[BonusIcon::Amber];
+            let (plain, enhanced) = (turn.mine.hand[0], turn.mine.hand[1]);
+            assert_eq!(turn.legal_actions(), vec![
+                Action::Play(plain, Flank::Right),
+                Action::Play(enhanced, Flank::Right),
+            ]);
+        });
+    }
 }
@@ -0,0 +1,146 @@
+use board::DeckBoard;
+use field::{Artifact, Creature};
+use game::{Card, House};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::mem;
+use turn::Action;
+
+// The public side of a board: everything either player can see
+pub struct BoardView<'a: 'b, 'b> {
+    pub houses: &'b [House],
+    pub amber: u32,
+    pub keys: u32,
+    pub chains: u32,
+    pub creatures: &'b [Creature<'a>],
+    pub artifacts: &'b [Artifact<'a>],
+    pub discard: &'b [Card<'a>],
+    pub purged: &'b [Card<'a>],
+    pub hand_size: usize,
+    pub deck_size: usize,
+    pub archives_size: usize,
+}
+
+impl<'a, 'b> BoardView<'a, 'b> {
+    pub fn new(board: &'b DeckBoard<'a>) -> Self {
+        BoardView {
+            houses: &board.deck_details.houses,
+            amber: board.amber,
+            keys: board.keys,
+            chains: board.chains,
+            creatures: &board.creatures,
+            artifacts: &board.artifacts,
+            discard: &board.discard,
+            purged: &board.purged,
+            hand_size: board.hand.len(),
+            deck_size: board.deck.len(),
+            archives_size: board.archives.len(),
+        }
+    }
+}
+
+// What the active player knows when making a decision. This is all a Player
+// is given, so AIs can't look at the opponent's hand or either deck's order.
+pub struct PlayerView<'a: 'b, 'b> {
+    pub mine: BoardView<'a, 'b>,
+    pub opponent: BoardView<'a, 'b>,
+    pub hand: &'b [Card<'a>],
+    // None while the house is still being chosen
+    pub house: Option<House>,
+    pub actions: Vec<Action<'a>>,
+    // How many actions this turn can be undone and redone
+    pub undos: usize,
+    pub redos: usize,
+    boards: (&'b DeckBoard<'a>, &'b DeckBoard<'a>),
+}
+
+impl<'a, 'b> PlayerView<'a, 'b> {
+    pub fn new(mine: &'b DeckBoard<'a>, opponent: &'b DeckBoard<'a>,
+               house: Option<House>, actions: Vec<Action<'a>>) -> Self {
+        PlayerView {
+            mine: BoardView::new(mine),
+            opponent: BoardView::new(opponent),
+            hand: &mine.hand,
+            house,
+            actions,
+            undos: 0,
+            redos: 0,
+            boards: (mine, opponent),
+        }
+    }
+
+    // The view at the start of a turn, before a house is chosen
+    pub fn choosing(mine: &'b DeckBoard<'a>, opponent: &'b DeckBoard<'a>) -> Self {
+        PlayerView::new(mine, opponent, None, Vec::new())
+    }
+
+    // Deals out a full game state consistent with this view, for AIs that
+    // search ahead. Our deck is shuffled, and the opponent's hand, archives
+    // and deck are redealt from the cards we haven't seen. Both boards get
+    // fresh shuffle seeds so later reshuffles aren't known either.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> (DeckBoard<'a>, DeckBoard<'a>) {
+        let mut mine = self.boards.0.clone();
+        mine.deck.shuffle(rng);
+        mine.rng = StdRng::seed_from_u64(rng.gen());
+
+        let mut opponent = self.boards.1.clone();
+        opponent.rng = StdRng::seed_from_u64(rng.gen());
+        let hand_size = opponent.hand.len();
+        let archives_size = opponent.archives.len();
+        let mut unseen = mem::take(&mut opponent.hand);
+        unseen.append(&mut opponent.archives);
+        unseen.append(&mut opponent.deck);
+        unseen.shuffle(rng);
+        opponent.deck = unseen.split_off(hand_size + archives_size);
+        opponent.archives = unseen.split_off(hand_size);
+        opponent.hand = unseen;
+        (mine, opponent)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PlayerView;
+    use api::test::deck_from;
+    use board::{DeckBoard, Zone};
+    use game::test::test_card;
+    use game::{House, Type};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_view_hides_opponent_hand() {
+        let deck = deck_from(test_card(House::Brobnar, Type::Creature, 0, 2, 0), 36);
+        let mine = DeckBoard::new(&deck);
+        let mut opponent = DeckBoard::new(&deck);
+        opponent.discard(0);
+        let view = PlayerView::choosing(&mine, &opponent);
+        assert_eq!(view.hand.len(), 6);
+        assert_eq!(view.mine.deck_size, 30);
+        assert_eq!(view.opponent.hand_size, 5);
+        assert_eq!(view.opponent.discard.len(), 1);
+        assert_eq!(view.opponent.houses, &deck.houses[..]);
+        assert_eq!(view.house, None);
+    }
+
+    #[test]
+    fn test_sample_keeps_zone_sizes() {
+        let deck = deck_from(test_card(House::Brobnar, Type::Creature, 0, 2, 0), 36);
+        let mine = DeckBoard::new(&deck);
+        let mut opponent = DeckBoard::new(&deck);
+        opponent.discard(0);
+        let card = opponent.hand[0];
+        opponent.hand.remove(0);
+        opponent.put_in(Zone::Archives, card);
+
+        let view = PlayerView::choosing(&mine, &opponent);
+        let (mine_sample, opponent_sample) = view.sample(&mut StdRng::seed_from_u64(1));
+        assert_eq!(mine_sample.hand, mine.hand);
+        assert_eq!(mine_sample.deck.len(), 30);
+        assert_eq!(opponent_sample.hand.len(), 4);
+        assert_eq!(opponent_sample.archives.len(), 1);
+        assert_eq!(opponent_sample.deck.len(), 30);
+        assert_eq!(opponent_sample.discard.len(), 1);
+    }
+}